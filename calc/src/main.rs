@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MIT
+
+mod repl;
+
+use calclib::context::{Arithmetic, EvaluationContext, Radix};
+use calclib::environment::Environment;
+use calclib::error::CalcError;
+use calclib::evaluator::EvaluationResult;
+use std::io::{self, BufRead, IsTerminal};
+use std::process::ExitCode;
+
+/// Exit status when an option is not understood or the input cannot be read.
+const EXIT_USAGE: u8 = 1;
+/// Exit status when an expression cannot be parsed.
+const EXIT_PARSE: u8 = 2;
+/// Exit status when an expression is parsed but cannot be evaluated.
+const EXIT_EVAL: u8 = 3;
+
+/// Decimal places of results in decimal arithmetic, as in the app.
+const DEFAULT_PRECISION: u32 = 2;
+
+const USAGE: &str = "\
+Usage: calc [--mode MODE]... [EXPRESSION]...
+
+Evaluates each EXPRESSION in turn, or each line of standard input when it is not
+a terminal, and prints the results. Without either, starts an interactive session.
+
+Options:
+  -m, --mode MODE  exact, decimal or integer arithmetic; deg, rad or grad angles;
+                   hex, dec, oct or bin numbers
+  -h, --help       print this help
+
+Exit status is 0 on success, 1 for a usage or input error, 2 if an expression
+cannot be parsed and 3 if it cannot be evaluated.
+";
+
+fn main() -> ExitCode {
+    let mut context = EvaluationContext::default();
+    let mut expressions = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "-m" | "--mode" => {
+                let Some(mode) = args.next() else {
+                    eprintln!("calc: {} needs a mode", arg);
+                    return ExitCode::from(EXIT_USAGE);
+                };
+                if set_mode(&mut context, &mode).is_err() {
+                    eprintln!("calc: unknown mode: {}", mode);
+                    return ExitCode::from(EXIT_USAGE);
+                }
+            }
+            // Anything else, including a negative number such as -5, is an expression
+            _ => expressions.push(arg),
+        }
+    }
+
+    let mut environment = Environment::new();
+
+    if !expressions.is_empty() {
+        return evaluate_all(expressions.into_iter().map(Ok), &context, &mut environment);
+    }
+
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        repl::run(context, environment)
+    } else {
+        evaluate_all(stdin.lock().lines(), &context, &mut environment)
+    }
+}
+
+/// Evaluates each line in order, printing results to standard output and errors to standard
+/// error. The exit status is that of the first error.
+fn evaluate_all(
+    lines: impl Iterator<Item = io::Result<String>>,
+    context: &EvaluationContext,
+    environment: &mut Environment,
+) -> ExitCode {
+    let mut status = None;
+
+    for line in lines {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("calc: {}", err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match environment.evaluate(line.clone(), context) {
+            Ok(result) if result.is_definition() => {}
+            Ok(result) => println!("{}", display(&result, context)),
+            Err(err) => {
+                eprintln!("calc: {}: {}", line.trim(), err);
+                status.get_or_insert(exit_status(&err));
+            }
+        }
+    }
+
+    status.map_or(ExitCode::SUCCESS, ExitCode::from)
+}
+
+/// The exit status for an error, telling input that could not be parsed from input that could
+/// not be evaluated.
+fn exit_status(err: &CalcError) -> u8 {
+    if err.is_syntax_error() {
+        EXIT_PARSE
+    } else {
+        EXIT_EVAL
+    }
+}
+
+/// The result written in the radix of the context.
+fn display(result: &EvaluationResult, context: &EvaluationContext) -> String {
+    match context.radix {
+        Radix::Decimal => result.value(),
+        radix => result
+            .to_radix(radix, None)
+            .unwrap_or_else(|| result.value()),
+    }
+}
+
+/// Changes one setting of context by name: the arithmetic, the angle unit or the radix.
+fn set_mode(context: &mut EvaluationContext, mode: &str) -> Result<(), ()> {
+    match mode {
+        "exact" => context.arithmetic = Arithmetic::Exact,
+        "decimal" => {
+            context.arithmetic = Arithmetic::Decimal {
+                precision: DEFAULT_PRECISION,
+                rounding: Default::default(),
+            }
+        }
+        "integer" => context.arithmetic = Arithmetic::Integer { word: None },
+        _ => {
+            if let Ok(angle_unit) = mode.parse() {
+                context.angle_unit = angle_unit;
+            } else {
+                context.radix = mode.parse()?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The settings of context by the names `set_mode` takes: "exact rad dec".
+fn describe_mode(context: &EvaluationContext) -> String {
+    let arithmetic = match context.arithmetic {
+        Arithmetic::Exact => "exact",
+        Arithmetic::Decimal { .. } => "decimal",
+        Arithmetic::Integer { .. } => "integer",
+    };
+
+    format!(
+        "{} {} {}",
+        arithmetic,
+        context.angle_unit.as_str(),
+        context.radix.as_str()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calclib::evaluator::evaluate;
+
+    #[test]
+    fn test_set_mode() {
+        let inputs = vec![
+            (vec![], "exact rad dec"),
+            (vec!["decimal"], "decimal rad dec"),
+            (vec!["integer", "hex"], "integer rad hex"),
+            (vec!["deg", "exact"], "exact deg dec"),
+            (vec!["grad", "bin", "rad"], "exact rad bin"),
+        ];
+
+        for i in inputs {
+            let mut context = EvaluationContext::default();
+            for mode in &i.0 {
+                assert_eq!(set_mode(&mut context, mode), Ok(()), "{}", mode);
+            }
+            assert_eq!(describe_mode(&context), i.1, "{:?}", i.0);
+        }
+
+        assert_eq!(
+            set_mode(&mut EvaluationContext::default(), "hexadecimal"),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let inputs = vec![
+            ("1 +", EXIT_PARSE),
+            ("(1", EXIT_PARSE),
+            ("1 $ 2", EXIT_PARSE),
+            ("1/0", EXIT_EVAL),
+            ("nope + 1", EXIT_EVAL),
+        ];
+
+        for i in inputs {
+            let err = evaluate(i.0.to_string(), &EvaluationContext::default()).unwrap_err();
+            assert_eq!(exit_status(&err), i.1, "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let mut context = EvaluationContext::default();
+        let result = evaluate("255".to_string(), &context).unwrap();
+        assert_eq!(display(&result, &context), "255");
+
+        set_mode(&mut context, "hex").unwrap();
+        assert_eq!(display(&result, &context), "FF");
+
+        let result = evaluate("1/2".to_string(), &context).unwrap();
+        assert_eq!(display(&result, &context), "0.5");
+    }
+}
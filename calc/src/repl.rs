@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MIT
+
+use crate::{EXIT_USAGE, describe_mode, display, set_mode};
+use calclib::context::EvaluationContext;
+use calclib::environment::Environment;
+use calclib::error::CalcError;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use std::process::ExitCode;
+
+const PROMPT: &str = "> ";
+
+const HELP: &str = "\
+Type an expression to evaluate it, `x = 5` to assign a variable or
+`f(x) = x^2` to define a function. `ans` is the last result.
+
+Commands:
+  :help         print this help
+  :vars         list the variables and functions
+  :mode         print the arithmetic, angle unit and radix
+  :mode NAME... change them: exact, decimal, integer, deg, rad, grad, hex, dec, oct, bin
+  :quit         leave, as does Ctrl+D
+";
+
+/// Reads expressions and commands from the terminal with line editing and history until the end
+/// of input.
+pub fn run(mut context: EvaluationContext, mut environment: Environment) -> ExitCode {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("calc: {}", err);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            // Ctrl+C abandons the line being typed
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("calc: {}", err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        if let Some(command) = line.strip_prefix(':') {
+            if !run_command(command, &mut context, &environment) {
+                break;
+            }
+            continue;
+        }
+
+        match environment.evaluate(line.to_string(), &context) {
+            Ok(result) if result.is_definition() => {}
+            Ok(result) => println!("{}", display(&result, &context)),
+            Err(err) => print_error(line, &err),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Runs a command typed after a `:`, returning false if it ends the session.
+fn run_command(command: &str, context: &mut EvaluationContext, environment: &Environment) -> bool {
+    let mut words = command.split_whitespace();
+
+    match words.next().unwrap_or("") {
+        "help" | "h" => print!("{}", HELP),
+        "vars" | "v" => {
+            for (name, value) in environment.variables() {
+                println!("{} = {}", name, display(value, context));
+            }
+            for (_, function) in environment.functions() {
+                println!("{}", function.definition());
+            }
+        }
+        "mode" | "m" => {
+            for mode in words {
+                if set_mode(context, mode).is_err() {
+                    eprintln!("unknown mode: {}", mode);
+                }
+            }
+            println!("{}", describe_mode(context));
+        }
+        "quit" | "q" => return false,
+        other => eprintln!("unknown command: :{} (try :help)", other),
+    }
+
+    true
+}
+
+/// Prints an error with carets under the part of the line responsible for it.
+fn print_error(line: &str, err: &CalcError) {
+    let span = err.span();
+    let before = line.get(..span.start).unwrap_or(line).chars().count();
+    let width = line
+        .get(span.start..span.end)
+        .map_or(0, |s| s.chars().count());
+
+    eprintln!(
+        "{}{}",
+        " ".repeat(PROMPT.len() + before),
+        "^".repeat(width.max(1))
+    );
+    eprintln!("error: {}", err);
+}
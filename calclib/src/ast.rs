@@ -1,21 +1,77 @@
+use crate::error::Span;
 use crate::token::Token;
+use crate::value::Value;
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum Expression {
     Number {
-        value: f64,
+        value: Value,
+        span: Span,
+    },
+    Identifier {
+        name: String,
+        span: Span,
     },
     Infix {
         left: Box<Expression>,
         operator: Token,
         right: Box<Expression>,
+        span: Span,
     },
     Prefix {
         operator: Token,
         right: Box<Expression>,
+        span: Span,
     },
     Unary {
         operator: Token,
         expression: Box<Expression>,
+        span: Span,
+    },
+    Call {
+        name: String,
+        args: Vec<Expression>,
+        span: Span,
+    },
+    Assign {
+        name: String,
+        value: Box<Expression>,
+        span: Span,
+    },
+    Define {
+        name: String,
+        params: Vec<String>,
+        body: Box<Expression>,
+        span: Span,
     },
 }
+
+impl Expression {
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Expression::Number { span, .. }
+            | Expression::Identifier { span, .. }
+            | Expression::Infix { span, .. }
+            | Expression::Prefix { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Call { span, .. }
+            | Expression::Assign { span, .. }
+            | Expression::Define { span, .. } => *span,
+        }
+    }
+
+    /// Widens the span of the expression to also cover span, e.g. its surrounding parentheses.
+    pub(crate) fn widen(mut self, span: Span) -> Self {
+        match &mut self {
+            Expression::Number { span: s, .. }
+            | Expression::Identifier { span: s, .. }
+            | Expression::Infix { span: s, .. }
+            | Expression::Prefix { span: s, .. }
+            | Expression::Unary { span: s, .. }
+            | Expression::Call { span: s, .. }
+            | Expression::Assign { span: s, .. }
+            | Expression::Define { span: s, .. } => *s = s.to(span),
+        }
+        self
+    }
+}
@@ -0,0 +1,42 @@
+use std::f64::consts;
+
+/// Named mathematical constants. Symbols such as `π` are read by the lexer as these names.
+const CONSTANTS: &[(&str, f64)] = &[
+    ("pi", consts::PI),
+    ("tau", consts::TAU),
+    ("e", consts::E),
+    // The golden ratio, (1 + √5) / 2
+    ("phi", 1.618_033_988_749_895),
+    ("sqrt2", consts::SQRT_2),
+    ("ln2", consts::LN_2),
+    ("ln10", consts::LN_10),
+];
+
+/// Looks up the value of a constant by name.
+pub(crate) fn lookup(name: &str) -> Option<f64> {
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, value)| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let inputs = vec![
+            ("pi", Some(consts::PI)),
+            ("tau", Some(2.0 * consts::PI)),
+            ("e", Some(consts::E)),
+            ("phi", Some((1.0 + 5f64.sqrt()) / 2.0)),
+            ("PI", None),
+            ("x", None),
+        ];
+
+        for i in inputs {
+            assert_eq!(lookup(i.0), i.1);
+        }
+    }
+}
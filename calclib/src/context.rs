@@ -0,0 +1,339 @@
+use crate::locale::Locale;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::One;
+use std::f64::consts::{PI, TAU};
+use std::str::FromStr;
+
+/// Options that control how an expression is evaluated.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvaluationContext {
+    /// The unit of the arguments of trigonometric functions and of the results of their inverses.
+    pub angle_unit: AngleUnit,
+    /// How numbers are represented during evaluation.
+    pub arithmetic: Arithmetic,
+    /// The characters used to write numbers and separate arguments in the input.
+    pub locale: Locale,
+    /// The base of numbers in the input that have no `0x`, `0o` or `0b` prefix.
+    pub radix: Radix,
+}
+
+impl EvaluationContext {
+    /// The word integers wrap around in, if the arithmetic has one.
+    pub(crate) fn word(&self) -> Option<Word> {
+        match self.arithmetic {
+            Arithmetic::Integer { word } => word,
+            _ => None,
+        }
+    }
+}
+
+/// How numbers are represented during evaluation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Arithmetic {
+    /// Exact integers and fractions, falling back to floating point for operations without an
+    /// exact result.
+    #[default]
+    Exact,
+    /// Base-10 decimals with results rounded to `precision` decimal places, for calculations such
+    /// as money where `1.10 * 3` must be `3.30`.
+    Decimal { precision: u32, rounding: Rounding },
+    /// Integers only, with every result truncated towards zero: 7 / 2 = 3. Results outside a
+    /// word wrap around, and without one integers are arbitrarily large.
+    Integer { word: Option<Word> },
+}
+
+impl Arithmetic {
+    /// The largest number of decimal places a decimal can hold.
+    pub const MAX_PRECISION: u32 = 28;
+}
+
+/// How a decimal result is rounded to its precision.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Rounding {
+    /// Round to the nearest value, and halves to the even neighbour (2.345 -> 2.34).
+    #[default]
+    HalfEven,
+    /// Round to the nearest value, and halves away from zero (2.345 -> 2.35).
+    HalfUp,
+    /// Discard the extra digits (2.349 -> 2.34).
+    Truncate,
+}
+
+impl Rounding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rounding::HalfEven => "half-even",
+            Rounding::HalfUp => "half-up",
+            Rounding::Truncate => "truncate",
+        }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-even" => Ok(Rounding::HalfEven),
+            "half-up" => Ok(Rounding::HalfUp),
+            "truncate" => Ok(Rounding::Truncate),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A fixed-width integer type such as a signed 32-bit integer, whose arithmetic wraps around on
+/// overflow as two's complement does.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Word {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl Default for Word {
+    fn default() -> Self {
+        Self {
+            bits: 64,
+            signed: true,
+        }
+    }
+}
+
+impl Word {
+    /// The widths offered by frontends.
+    pub const SIZES: [u32; 5] = [8, 16, 32, 64, 128];
+
+    /// The number of values of the word, 2^bits.
+    fn modulus(self) -> BigInt {
+        BigInt::one() << self.bits
+    }
+
+    /// Whether n is in the range of the word: -128 to 127 for a signed 8-bit integer.
+    pub(crate) fn contains(self, n: &BigInt) -> bool {
+        *n == self.wrap(n)
+    }
+
+    /// Wraps n into the range of the word: 128 is -128 as a signed 8-bit integer.
+    pub(crate) fn wrap(self, n: &BigInt) -> BigInt {
+        let unsigned = self.to_unsigned(n);
+
+        if self.signed && unsigned.bits() == u64::from(self.bits) {
+            unsigned - self.modulus()
+        } else {
+            unsigned
+        }
+    }
+
+    /// The bits of n read as an unsigned integer: -1 is 255 as an 8-bit integer.
+    pub(crate) fn to_unsigned(self, n: &BigInt) -> BigInt {
+        n.mod_floor(&self.modulus())
+    }
+}
+
+/// The base in which integers are written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    #[default]
+    Decimal,
+    /// Digits above 9 are the capital letters A to F, so that words such as `abs` are still read
+    /// as names.
+    Hexadecimal,
+}
+
+impl Radix {
+    pub const ALL: [Radix; 4] = [
+        Radix::Hexadecimal,
+        Radix::Decimal,
+        Radix::Octal,
+        Radix::Binary,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Radix::Binary => "bin",
+            Radix::Octal => "oct",
+            Radix::Decimal => "dec",
+            Radix::Hexadecimal => "hex",
+        }
+    }
+
+    /// The number of digits: 2, 8, 10 or 16.
+    pub fn value(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
+        }
+    }
+
+    /// Whether ch is a digit of this radix. Lowercase letters are not digits.
+    pub fn is_digit(&self, ch: char) -> bool {
+        ch.is_digit(self.value()) && !ch.is_ascii_lowercase()
+    }
+}
+
+impl FromStr for Radix {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bin" => Ok(Radix::Binary),
+            "oct" => Ok(Radix::Octal),
+            "dec" => Ok(Radix::Decimal),
+            "hex" => Ok(Radix::Hexadecimal),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The unit in which angles are measured.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AngleUnit {
+    Degrees,
+    #[default]
+    Radians,
+    Gradians,
+}
+
+impl AngleUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => "deg",
+            AngleUnit::Radians => "rad",
+            AngleUnit::Gradians => "grad",
+        }
+    }
+
+    /// Converts an angle in this unit to radians.
+    pub(crate) fn to_radians(self, angle: f64) -> f64 {
+        match self {
+            AngleUnit::Degrees => angle.to_radians(),
+            AngleUnit::Radians => angle,
+            AngleUnit::Gradians => angle * PI / 200.0,
+        }
+    }
+
+    /// Converts an angle in radians to this unit.
+    pub(crate) fn radians_to_unit(self, angle: f64) -> f64 {
+        match self {
+            AngleUnit::Degrees => angle.to_degrees(),
+            AngleUnit::Radians => angle,
+            AngleUnit::Gradians => angle * 200.0 / PI,
+        }
+    }
+
+    /// Converts an angle in this unit to a number of full turns.
+    pub(crate) fn to_turns(self, angle: f64) -> f64 {
+        match self {
+            AngleUnit::Degrees => angle / 360.0,
+            AngleUnit::Radians => angle / TAU,
+            AngleUnit::Gradians => angle / 400.0,
+        }
+    }
+}
+
+impl FromStr for AngleUnit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deg" => Ok(AngleUnit::Degrees),
+            "rad" => Ok(AngleUnit::Radians),
+            "grad" => Ok(AngleUnit::Gradians),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_unit_conversions() {
+        let inputs = vec![
+            (AngleUnit::Degrees, 180.0, PI),
+            (AngleUnit::Radians, PI, PI),
+            (AngleUnit::Gradians, 200.0, PI),
+            (AngleUnit::Degrees, -90.0, -PI / 2.0),
+            (AngleUnit::Gradians, 100.0, PI / 2.0),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.to_radians(i.1), i.2);
+            assert_eq!(i.0.radians_to_unit(i.2), i.1);
+            assert_eq!(i.0.to_turns(i.1), 0.5 * i.2 / PI);
+        }
+    }
+
+    #[test]
+    fn test_angle_unit_strings() {
+        for unit in [AngleUnit::Degrees, AngleUnit::Radians, AngleUnit::Gradians] {
+            assert_eq!(unit.as_str().parse::<AngleUnit>(), Ok(unit));
+        }
+
+        assert_eq!("".parse::<AngleUnit>(), Err(()));
+    }
+
+    #[test]
+    fn test_rounding_strings() {
+        for rounding in [Rounding::HalfEven, Rounding::HalfUp, Rounding::Truncate] {
+            assert_eq!(rounding.as_str().parse::<Rounding>(), Ok(rounding));
+        }
+
+        assert_eq!("up".parse::<Rounding>(), Err(()));
+    }
+
+    #[test]
+    fn test_word() {
+        let word = |bits, signed| Word { bits, signed };
+
+        let inputs = vec![
+            (word(8, true), 127_i64, 127_i64, 127_i64),
+            (word(8, true), 128, -128, 128),
+            (word(8, true), -129, 127, 127),
+            (word(8, true), -1, -1, 255),
+            (word(8, false), -1, 255, 255),
+            (word(8, false), 256, 0, 0),
+            (word(16, true), 40000, -25536, 40000),
+            (word(32, false), -2, 4294967294, 4294967294),
+        ];
+
+        for i in inputs {
+            let n = BigInt::from(i.1);
+            assert_eq!(i.0.wrap(&n), BigInt::from(i.2), "{:?} {}", i.0, i.1);
+            assert_eq!(i.0.to_unsigned(&n), BigInt::from(i.3), "{:?} {}", i.0, i.1);
+            assert_eq!(i.0.contains(&n), i.1 == i.2, "{:?} {}", i.0, i.1);
+        }
+
+        let min = -(BigInt::one() << 127u32);
+        assert_eq!(word(128, true).wrap(&-&min), min);
+    }
+
+    #[test]
+    fn test_radix() {
+        for radix in Radix::ALL {
+            assert_eq!(radix.as_str().parse::<Radix>(), Ok(radix));
+        }
+
+        let inputs = vec![
+            (Radix::Binary, '1', true),
+            (Radix::Binary, '2', false),
+            (Radix::Octal, '7', true),
+            (Radix::Octal, '8', false),
+            (Radix::Decimal, '9', true),
+            (Radix::Decimal, 'A', false),
+            (Radix::Hexadecimal, 'F', true),
+            (Radix::Hexadecimal, 'f', false),
+            (Radix::Hexadecimal, 'G', false),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.is_digit(i.1), i.2, "{:?} {}", i.0, i.1);
+        }
+    }
+}
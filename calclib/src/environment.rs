@@ -0,0 +1,422 @@
+use crate::ast::Expression;
+use crate::constants;
+use crate::context::EvaluationContext;
+use crate::error::CalcError;
+use crate::evaluator::{self, EvaluationResult};
+use crate::functions;
+use crate::lexer::Lexer;
+use crate::locale::Locale;
+use crate::token::Token;
+use std::collections::BTreeMap;
+
+/// The variable bound to the result of the last successful evaluation.
+pub const ANS: &str = "ans";
+
+/// A function defined by the user, such as `f(x) = x^2 + 1`.
+#[derive(Clone, Debug)]
+pub struct Function {
+    params: Vec<String>,
+    body: Expression,
+    definition: String,
+}
+
+impl Function {
+    pub(crate) fn new(params: Vec<String>, body: Expression, definition: String) -> Self {
+        Self {
+            params,
+            body,
+            definition,
+        }
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    pub(crate) fn body(&self) -> &Expression {
+        &self.body
+    }
+
+    /// The text of the definition, which defines the function again when evaluated.
+    pub fn definition(&self) -> &str {
+        &self.definition
+    }
+}
+
+/// Named values and functions that persist between evaluations in a session.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    variables: BTreeMap<String, EvaluationResult>,
+    functions: BTreeMap<String, Function>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluates input, storing the value of an assignment such as `x = 5` or the function
+    /// defined by `f(x) = x^2 + 1`, and binding `ans` to the result.
+    pub fn evaluate(
+        &mut self,
+        input: String,
+        context: &EvaluationContext,
+    ) -> Result<EvaluationResult, CalcError> {
+        evaluator::evaluate_in(input, context, self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EvaluationResult> {
+        self.variables.get(name)
+    }
+
+    pub(crate) fn set(&mut self, name: String, value: EvaluationResult) {
+        self.variables.insert(name, value);
+    }
+
+    /// The variables currently bound, sorted by name.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &EvaluationResult)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.variables.remove(name).is_some()
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+
+    pub(crate) fn define(&mut self, name: String, function: Function) {
+        self.functions.insert(name, function);
+    }
+
+    /// The functions currently defined, sorted by name.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &Function)> {
+        self.functions
+            .iter()
+            .map(|(name, function)| (name.as_str(), function))
+    }
+
+    pub fn remove_function(&mut self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    /// Removes all variables, including `ans`, keeping the functions.
+    pub fn clear_variables(&mut self) {
+        self.variables.clear();
+    }
+
+    /// Removes all variables and functions.
+    pub fn clear(&mut self) {
+        self.variables.clear();
+        self.functions.clear();
+    }
+}
+
+/// Whether name belongs to a constant, a built-in function or `ans`, and so cannot be assigned.
+pub(crate) fn is_reserved(name: &str) -> bool {
+    name == ANS || constants::lookup(name).is_some() || functions::lookup(name).is_some()
+}
+
+/// Whether input can be assigned to, such as the `x` in `x = 5` or the `f(x)` in
+/// `f(x) = x^2 + 1`. Frontends use this to tell an `=` that starts an assignment from one that
+/// asks for the result. Parameters are separated as arguments are in locale.
+pub fn is_assignment_target(input: &str, locale: &Locale) -> bool {
+    let context = EvaluationContext {
+        locale: *locale,
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_context(input.to_string(), &context);
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.next_token() {
+            Ok((Token::Eof, _)) => break,
+            Ok((token, _)) => tokens.push(token),
+            Err(_) => return false,
+        }
+    }
+
+    match tokens.as_slice() {
+        [Token::Ident(name)] => !is_reserved(name),
+        [
+            Token::Ident(name),
+            Token::LParen,
+            params @ ..,
+            Token::RParen,
+        ] => {
+            // Parameters are names separated by commas: f(), f(x), f(x, y)
+            !is_reserved(name)
+                && params.iter().enumerate().all(|(i, token)| match token {
+                    Token::Ident(param) => i % 2 == 0 && !is_reserved(param),
+                    Token::Comma => i % 2 == 1 && i + 1 < params.len(),
+                    _ => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Span;
+
+    #[test]
+    fn test_environment_assignment() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        let result = env.evaluate("x = 5".to_string(), &context).unwrap();
+        assert_eq!(result.int_value(), Some(5));
+        assert_eq!(env.get("x").unwrap().int_value(), Some(5));
+
+        let result = env.evaluate("2x + 1".to_string(), &context).unwrap();
+        assert_eq!(result.int_value(), Some(11));
+
+        let result = env.evaluate("x = x * 2".to_string(), &context).unwrap();
+        assert_eq!(result.int_value(), Some(10));
+        assert_eq!(env.get("x").unwrap().int_value(), Some(10));
+    }
+
+    #[test]
+    fn test_environment_ans() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        let result = env.evaluate("ans".to_string(), &context);
+        assert_eq!(
+            result.err().unwrap(),
+            CalcError::UnknownIdentifier {
+                name: "ans".to_string(),
+                span: Span::new(0, 3),
+            }
+        );
+
+        env.evaluate("6*7".to_string(), &context).unwrap();
+        assert_eq!(env.get(ANS).unwrap().int_value(), Some(42));
+
+        let result = env.evaluate("ans + 1".to_string(), &context).unwrap();
+        assert_eq!(result.int_value(), Some(43));
+        assert_eq!(env.get(ANS).unwrap().int_value(), Some(43));
+
+        // A failed evaluation leaves ans untouched
+        assert!(env.evaluate("1/0".to_string(), &context).is_err());
+        assert_eq!(env.get(ANS).unwrap().int_value(), Some(43));
+    }
+
+    #[test]
+    fn test_environment_reserved_names() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        for input in ["pi = 3", "ans = 1", "sin = 2"] {
+            let result = env.evaluate(input.to_string(), &context);
+            assert_eq!(
+                result.err().unwrap(),
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, input.len()),
+                },
+                "{}",
+                input
+            );
+        }
+
+        assert_eq!(env.variables().count(), 0);
+    }
+
+    #[test]
+    fn test_environment_variables() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        env.evaluate("b = 2".to_string(), &context).unwrap();
+        env.evaluate("a = 1".to_string(), &context).unwrap();
+
+        let names: Vec<&str> = env.variables().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["a", "ans", "b"]);
+
+        assert!(env.remove("a"));
+        assert!(!env.remove("a"));
+        assert!(env.get("a").is_none());
+
+        env.evaluate("f(x) = x".to_string(), &context).unwrap();
+        env.clear_variables();
+        assert_eq!(env.variables().count(), 0);
+        assert_eq!(env.functions().count(), 1);
+
+        env.evaluate("b = 2".to_string(), &context).unwrap();
+        env.clear();
+        assert_eq!(env.variables().count(), 0);
+        assert_eq!(env.functions().count(), 0);
+    }
+
+    #[test]
+    fn test_environment_functions() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        let result = env
+            .evaluate("f(x) = x^2 + 1".to_string(), &context)
+            .unwrap();
+        assert!(result.is_definition());
+        assert!(env.get(ANS).is_none());
+        assert_eq!(env.function("f").unwrap().definition(), "f(x) = x^2 + 1");
+
+        env.evaluate("a = 2".to_string(), &context).unwrap();
+        env.evaluate("hyp(a, b) = sqrt(a^2 + b^2)".to_string(), &context)
+            .unwrap();
+        env.evaluate("g() = f(a)".to_string(), &context).unwrap();
+
+        let inputs = vec![
+            ("f(3)", 10),
+            ("2f(1) + 1", 5),
+            ("f(f(1))", 5),
+            ("hyp(3, 4)", 5),
+            ("a", 2),
+            ("g()", 5),
+        ];
+
+        for i in inputs {
+            let result = env.evaluate(i.0.to_string(), &context).unwrap();
+            assert_eq!(result.int_value(), Some(i.1), "{}", i.0);
+        }
+
+        // Redefining a function replaces it
+        env.evaluate("f(x) = x".to_string(), &context).unwrap();
+        assert_eq!(
+            env.evaluate("f(3)".to_string(), &context)
+                .unwrap()
+                .int_value(),
+            Some(3)
+        );
+
+        let names: Vec<&str> = env.functions().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["f", "g", "hyp"]);
+
+        assert!(env.remove_function("g"));
+        assert!(!env.remove_function("g"));
+        assert!(env.function("g").is_none());
+    }
+
+    #[test]
+    fn test_environment_function_errors() {
+        let context = EvaluationContext::default();
+        let mut env = Environment::new();
+
+        env.evaluate("f(x) = f(x)".to_string(), &context).unwrap();
+        env.evaluate("even(n) = 1 + odd(n)".to_string(), &context)
+            .unwrap();
+        env.evaluate("odd(n) = 2 * even(n)".to_string(), &context)
+            .unwrap();
+        env.evaluate("g(x) = x + y".to_string(), &context).unwrap();
+
+        let inputs = vec![
+            (
+                "f(1)",
+                CalcError::RecursionLimit {
+                    span: Span::new(7, 11),
+                },
+            ),
+            (
+                "even(1)",
+                CalcError::RecursionLimit {
+                    span: Span::new(13, 20),
+                },
+            ),
+            (
+                "f(1, 2)",
+                CalcError::ArgumentCount {
+                    name: "f".to_string(),
+                    found: 2,
+                    span: Span::new(0, 7),
+                },
+            ),
+            (
+                "g(1)",
+                CalcError::UnknownIdentifier {
+                    name: "y".to_string(),
+                    span: Span::new(11, 12),
+                },
+            ),
+            (
+                "sin(x) = x",
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, 10),
+                },
+            ),
+            (
+                "h(pi) = 1",
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, 9),
+                },
+            ),
+            (
+                "h(x, x) = x",
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, 11),
+                },
+            ),
+            (
+                "1 + f(x) = x",
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, 8),
+                },
+            ),
+        ];
+
+        for i in inputs {
+            let result = env.evaluate(i.0.to_string(), &context);
+            assert_eq!(result.err().unwrap(), i.1, "{}", i.0);
+        }
+
+        // The parameter of a function is only visible inside its body
+        assert_eq!(
+            env.evaluate("x".to_string(), &context).err().unwrap(),
+            CalcError::UnknownIdentifier {
+                name: "x".to_string(),
+                span: Span::new(0, 1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_is_assignment_target() {
+        let inputs = vec![
+            ("x", true),
+            (" rate ", true),
+            ("x1", true),
+            ("", false),
+            ("2", false),
+            ("x+1", false),
+            ("pi", false),
+            ("ans", false),
+            ("sqrt", false),
+            ("@", false),
+            ("f(x)", true),
+            ("f()", true),
+            ("g(x, y)", true),
+            ("f(2)", false),
+            ("f(x,)", false),
+            ("f(x y)", false),
+            ("f(x", false),
+            ("sin(x)", false),
+            ("f(e)", false),
+        ];
+
+        for i in inputs {
+            assert_eq!(
+                is_assignment_target(i.0, &Locale::default()),
+                i.1,
+                "{}",
+                i.0
+            );
+        }
+
+        let german = Locale::from_language("de");
+        assert!(is_assignment_target("g(x; y)", &german));
+        assert!(!is_assignment_target("g(x, y)", &german));
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::ops::Range;
+
+/// A byte range into the input that was evaluated.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub(crate) fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns a span covering both self and other.
+    pub(crate) fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.start..span.end
+    }
+}
+
+/// Errors produced while lexing, parsing or evaluating an expression. Every variant carries the
+/// span of the input responsible for the error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalcError {
+    /// A character that is not part of the expression language.
+    UnknownCharacter { ch: char, span: Span },
+    /// A numeric literal that could not be read, such as `1.2.3`.
+    InvalidNumber { span: Span },
+    /// A name that is not a known keyword or function.
+    UnknownIdentifier { name: String, span: Span },
+    /// A token that cannot appear at this position, such as the `*` in `*3`.
+    UnexpectedToken { span: Span },
+    /// The input ended where an operand was expected, such as `3-`.
+    UnexpectedEnd { span: Span },
+    /// An opening parenthesis that is never closed.
+    UnclosedParen { span: Span },
+    /// A closing parenthesis without a matching opening one.
+    UnbalancedParen { span: Span },
+    /// The left side of `=` is not a name that can be assigned to.
+    InvalidAssignment { span: Span },
+    /// A function called with the wrong number of arguments.
+    ArgumentCount {
+        name: String,
+        found: usize,
+        span: Span,
+    },
+    /// A user-defined function that calls itself too deeply, such as `f(x) = f(x)`.
+    RecursionLimit { span: Span },
+    /// A division by zero, such as `1/0`, `7 mod 0` or `0^-1`.
+    DivisionByZero { span: Span },
+    /// The operation has no real result, such as `(-8)^0.5`.
+    DomainError { span: Span },
+    /// The result is too large to represent.
+    Overflow { span: Span },
+    /// An operand of a bitwise operator that is not an integer, such as the `2.5` in `2.5 & 1`.
+    NotAnInteger { span: Span },
+}
+
+impl CalcError {
+    /// The part of the input responsible for the error.
+    pub fn span(&self) -> Span {
+        match self {
+            CalcError::UnknownCharacter { span, .. }
+            | CalcError::InvalidNumber { span }
+            | CalcError::UnknownIdentifier { span, .. }
+            | CalcError::UnexpectedToken { span }
+            | CalcError::UnexpectedEnd { span }
+            | CalcError::UnclosedParen { span }
+            | CalcError::UnbalancedParen { span }
+            | CalcError::InvalidAssignment { span }
+            | CalcError::ArgumentCount { span, .. }
+            | CalcError::RecursionLimit { span }
+            | CalcError::DivisionByZero { span }
+            | CalcError::DomainError { span }
+            | CalcError::Overflow { span }
+            | CalcError::NotAnInteger { span } => *span,
+        }
+    }
+
+    /// Whether the input is the start of an expression that has not been finished, such as `3-` or
+    /// `(399`, rather than one that is wrong.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self,
+            CalcError::UnexpectedEnd { .. } | CalcError::UnclosedParen { .. }
+        )
+    }
+
+    /// Whether the input could not be read as an expression, as opposed to an expression that
+    /// could not be evaluated.
+    pub fn is_syntax_error(&self) -> bool {
+        matches!(
+            self,
+            CalcError::UnknownCharacter { .. }
+                | CalcError::InvalidNumber { .. }
+                | CalcError::UnexpectedToken { .. }
+                | CalcError::UnexpectedEnd { .. }
+                | CalcError::UnclosedParen { .. }
+                | CalcError::UnbalancedParen { .. }
+                | CalcError::InvalidAssignment { .. }
+        )
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::UnknownCharacter { ch, .. } => write!(f, "Unknown character: {}", ch),
+            CalcError::InvalidNumber { .. } => write!(f, "Invalid number"),
+            CalcError::UnknownIdentifier { name, .. } => write!(f, "Unknown identifier: {}", name),
+            CalcError::UnexpectedToken { .. } => write!(f, "Unexpected token"),
+            CalcError::UnexpectedEnd { .. } => write!(f, "Incomplete expression"),
+            CalcError::UnclosedParen { .. } => write!(f, "Missing closing parenthesis"),
+            CalcError::UnbalancedParen { .. } => write!(f, "Unbalanced parenthesis"),
+            CalcError::InvalidAssignment { .. } => write!(f, "Cannot assign to this"),
+            CalcError::ArgumentCount { name, found, .. } => {
+                write!(f, "Wrong number of arguments to {}: {}", name, found)
+            }
+            CalcError::RecursionLimit { .. } => write!(f, "Recursion limit exceeded"),
+            CalcError::DivisionByZero { .. } => write!(f, "Division by zero"),
+            CalcError::DomainError { .. } => write!(f, "Domain error"),
+            CalcError::Overflow { .. } => write!(f, "Overflow"),
+            CalcError::NotAnInteger { .. } => write!(f, "Expected an integer"),
+        }
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::EvaluationContext;
+    use crate::evaluator::evaluate;
+
+    #[test]
+    fn test_span_to() {
+        let inputs = vec![
+            (Span::new(0, 1), Span::new(2, 4), Span::new(0, 4)),
+            (Span::new(2, 4), Span::new(0, 1), Span::new(0, 4)),
+            (Span::new(1, 3), Span::new(1, 3), Span::new(1, 3)),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.to(i.1), i.2);
+        }
+    }
+
+    #[test]
+    fn test_error_span_and_message() {
+        let err = CalcError::DivisionByZero {
+            span: Span::new(3, 4),
+        };
+
+        assert_eq!(err.span(), Span::new(3, 4));
+        assert_eq!(Range::from(err.span()), 3..4);
+        assert_eq!(err.to_string(), "Division by zero");
+    }
+
+    #[test]
+    fn test_error_is_syntax_error() {
+        let span = Span::new(0, 1);
+        let inputs = vec![
+            (CalcError::UnknownCharacter { ch: '$', span }, true),
+            (CalcError::UnexpectedEnd { span }, true),
+            (CalcError::UnclosedParen { span }, true),
+            (
+                CalcError::UnknownIdentifier {
+                    name: "x".to_string(),
+                    span,
+                },
+                false,
+            ),
+            (CalcError::DivisionByZero { span }, false),
+            (CalcError::Overflow { span }, false),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.is_syntax_error(), i.1, "{:?}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_error_is_incomplete() {
+        let inputs = vec![
+            ("3-", true),
+            ("(399", true),
+            ("max(1, 2", true),
+            ("f(x) =", true),
+            ("3)", false),
+            ("*3", false),
+            ("1 $", false),
+            ("1/0", false),
+        ];
+
+        for i in inputs {
+            let err = evaluate(i.0.to_string(), &EvaluationContext::default()).unwrap_err();
+            assert_eq!(err.is_incomplete(), i.1, "{} {:?}", i.0, err);
+        }
+    }
+}
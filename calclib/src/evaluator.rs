@@ -1,122 +1,582 @@
-use crate::ast::Expression::{Infix, Number, Prefix, Unary};
+use crate::ast::Expression::{Assign, Call, Define, Identifier, Infix, Number, Prefix, Unary};
+use crate::constants;
+use crate::context::{EvaluationContext, Radix, Word};
+use crate::environment::{self, ANS, Environment, Function};
+use crate::error::{CalcError, Span};
+use crate::format::Format;
+use crate::functions::{self, Angle};
 use crate::parser::Parser;
 use crate::utils::{change_sign, is_integer, is_negative};
+use crate::value::{self, Value};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
 use statrs::function::{factorial, gamma::gamma};
+use std::cell::Cell;
+use std::ops::Range;
 
+/// How deeply user-defined functions may call each other before evaluation gives up.
+const MAX_CALL_DEPTH: usize = 100;
+
+/// The width of the word whose bits `rol` and `ror` rotate when evaluation has no word size.
+const ROTATE_BITS: u32 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct EvaluationResult {
-    value: Option<f64>,
+    value: Option<Value>,
+    overflowed: bool,
 }
 
 impl EvaluationResult {
+    /// Whether the input defined a function rather than producing a value.
+    pub fn is_definition(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Whether an integer result was too large for the word size and wrapped around.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
     pub fn int_value(&self) -> Option<i64> {
-        if is_integer(self.value) && self.value.map_or(false, |f| f.abs() <= i64::MAX as f64) {
-            return self.value.map(|f| f.trunc() as i64);
+        match self.value.as_ref()? {
+            Value::Integer(n) => n.to_i64(),
+            Value::Float(f) if is_integer(Some(*f)) && f.abs() <= i64::MAX as f64 => {
+                Some(f.trunc() as i64)
+            }
+            Value::Decimal(d) if d.is_integer() => d.to_i64(),
+            Value::Rational(_) | Value::Decimal(_) | Value::Float(_) => None,
         }
-        None
+    }
+
+    /// The value as a fraction such as "1/3", if it is exact but not an integer.
+    pub fn fraction(&self) -> Option<String> {
+        self.value.as_ref()?.fraction()
+    }
+
+    /// The value written in radix, if it is an integer: 255 is "FF" in hexadecimal. With a word,
+    /// negative values are written in other radixes as their two's-complement bits: -1 is "FF" as
+    /// an 8-bit integer.
+    pub fn to_radix(&self, radix: Radix, word: Option<Word>) -> Option<String> {
+        let mut n = self.value.as_ref()?.to_integer()?;
+        if let Some(word) = word
+            && radix != Radix::Decimal
+        {
+            n = word.to_unsigned(&n);
+        }
+
+        Some(n.to_str_radix(radix.value()).to_uppercase())
+    }
+
+    /// The two's-complement bits of the value in word, least significant first, if it is an
+    /// integer.
+    pub fn bits(&self, word: Word) -> Option<Vec<bool>> {
+        let n = word.to_unsigned(&self.value.as_ref()?.to_integer()?);
+        Some((0..u64::from(word.bits)).map(|bit| n.bit(bit)).collect())
+    }
+
+    /// The integer in word whose two's-complement bits, least significant first, are bits.
+    pub fn from_bits(bits: &[bool], word: Word) -> EvaluationResult {
+        let n = bits
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |n, &bit| (n << 1u32) + u32::from(bit));
+        EvaluationResult::from(Value::Integer(word.wrap(&n)))
+    }
+
+    /// The value as floating point, which may lose precision for large integers.
+    pub fn to_f64(&self) -> Option<f64> {
+        self.value.as_ref().map(Value::to_f64)
     }
 
     pub fn value(&self) -> String {
-        if let Some(f) = self.value {
-            if is_integer(self.value) {
-                if f.abs() <= i64::MAX as f64 {
-                    return format!("{}", f.trunc() as i64);
-                } else {
-                    return format!("{:e}", f);
-                }
-            } else {
-                return format!("{}", f);
+        self.format(&Format::default())
+    }
+
+    /// The value written with the options of format, or nothing for a definition.
+    pub fn format(&self, format: &Format) -> String {
+        match &self.value {
+            Some(value) => format.value(value),
+            None => String::new(),
+        }
+    }
+}
+
+impl From<Value> for EvaluationResult {
+    fn from(value: Value) -> Self {
+        EvaluationResult {
+            value: Some(value),
+            overflowed: false,
+        }
+    }
+}
+
+pub fn evaluate(input: String, context: &EvaluationContext) -> Result<EvaluationResult, CalcError> {
+    evaluate_in(input, context, &mut Environment::new())
+}
+
+/// Evaluates input, storing assignments and function definitions in environment and binding `ans`
+/// to the result.
+pub(crate) fn evaluate_in(
+    input: String,
+    context: &EvaluationContext,
+    environment: &mut Environment,
+) -> Result<EvaluationResult, CalcError> {
+    let mut parser = Parser::with_context(context.clone());
+    let end = input.len();
+
+    let result = match parser.parse(input.clone())? {
+        Some(Assign { name, value, span }) => {
+            if environment::is_reserved(&name) {
+                return Err(CalcError::InvalidAssignment { span });
             }
-        } else {
-            "NaN".to_string()
+
+            let result = evaluate_result(*value, context, environment)?;
+            environment.set(name, result.clone());
+            result
+        }
+        Some(Define {
+            name,
+            params,
+            body,
+            span,
+        }) => {
+            let duplicate = params
+                .iter()
+                .enumerate()
+                .any(|(i, param)| params[..i].contains(param));
+            if environment::is_reserved(&name)
+                || duplicate
+                || params.iter().any(|param| environment::is_reserved(param))
+            {
+                return Err(CalcError::InvalidAssignment { span });
+            }
+
+            let definition = input[Range::from(span)].to_string();
+            environment.define(name, Function::new(params, *body, definition));
+
+            // A definition has no value, so ans keeps the previous result
+            return Ok(EvaluationResult {
+                value: None,
+                overflowed: false,
+            });
+        }
+        Some(ex) => evaluate_result(ex, context, environment)?,
+        None => {
+            return Err(CalcError::UnexpectedEnd {
+                span: Span::new(end, end),
+            });
+        }
+    };
+
+    environment.set(ANS.to_string(), result.clone());
+
+    Ok(result)
+}
+
+/// Evaluates the expression of the input, rounding its value to the precision of decimal
+/// arithmetic.
+fn evaluate_result(
+    expression: crate::ast::Expression,
+    context: &EvaluationContext,
+    environment: &Environment,
+) -> Result<EvaluationResult, CalcError> {
+    let overflowed = Cell::new(false);
+    let value = evaluate_expression(expression, context, &Scope::new(environment, &overflowed))?;
+
+    Ok(EvaluationResult {
+        value: Some(value.round(context.arithmetic)),
+        overflowed: overflowed.get(),
+    })
+}
+
+/// The names visible to an expression being evaluated.
+struct Scope<'a> {
+    environment: &'a Environment,
+    /// The arguments of the user-defined function being evaluated, by parameter name.
+    arguments: Vec<(String, Value)>,
+    /// The number of user-defined function calls being evaluated.
+    depth: usize,
+    /// Set when an integer wraps around the word size.
+    overflowed: &'a Cell<bool>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(environment: &'a Environment, overflowed: &'a Cell<bool>) -> Self {
+        Self {
+            environment,
+            arguments: Vec::new(),
+            depth: 0,
+            overflowed,
         }
     }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.arguments
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.environment.get(name)?.value.as_ref())
+    }
 }
 
-pub fn evaluate(input: String) -> Result<EvaluationResult, String> {
-    let mut parser = Parser::new();
-    let parse_val = parser.parse(input);
-
-    match parse_val {
-        Err(e) => Err(e),
-        Ok(v) => {
-            // println!("Parser output: {:?}", v);
-            match v {
-                Some(ex) => evaluate_expression(ex),
-                None => Err("Invalid expression".to_string()),
+fn evaluate_expression(
+    expression: crate::ast::Expression,
+    context: &EvaluationContext,
+    scope: &Scope,
+) -> Result<Value, CalcError> {
+    let overflows = can_overflow(&expression);
+
+    // Every intermediate value, including literals and function results, uses the arithmetic of
+    // the context
+    let value = evaluate_value(expression, context, scope)?.with_arithmetic(context.arithmetic);
+
+    // Integers outside the word wrap around. Only arithmetic overflows: a literal such as 0xFF or
+    // the result of a bitwise operator is a pattern of bits that is read in the word.
+    match (context.word(), value) {
+        (Some(word), Value::Integer(n)) if !word.contains(&n) => {
+            if overflows {
+                scope.overflowed.set(true);
             }
+            Ok(Value::Integer(word.wrap(&n)))
         }
+        (_, value) => Ok(value),
+    }
+}
+
+/// Whether the result of expression is a number that may be too large for a word, rather than a
+/// pattern of bits.
+fn can_overflow(expression: &crate::ast::Expression) -> bool {
+    match expression {
+        Infix { operator, .. } => matches!(
+            operator,
+            crate::token::Token::Plus
+                | crate::token::Token::Minus
+                | crate::token::Token::Multiply
+                | crate::token::Token::Divide
+                | crate::token::Token::Mod
+                | crate::token::Token::Caret
+        ),
+        Prefix { operator, .. } => matches!(operator, crate::token::Token::Minus),
+        Unary { .. } | Call { .. } => true,
+        _ => false,
     }
 }
 
-fn evaluate_expression(expression: crate::ast::Expression) -> Result<EvaluationResult, String> {
+fn evaluate_value(
+    expression: crate::ast::Expression,
+    context: &EvaluationContext,
+    scope: &Scope,
+) -> Result<Value, CalcError> {
     match expression {
-        Number { value } => Ok(EvaluationResult { value: Some(value) }),
+        Number { value, .. } => Ok(value),
+        Identifier { name, span } => {
+            if let Some(value) = scope.get(&name) {
+                return Ok(value.clone());
+            }
+
+            match constants::lookup(&name) {
+                Some(value) => Ok(Value::Float(value)),
+                None => Err(CalcError::UnknownIdentifier { name, span }),
+            }
+        }
         Infix {
             left,
             operator,
             right,
+            span,
         } => {
-            let left_val = evaluate_expression(*left)?;
-            let right_val = evaluate_expression(*right)?;
+            // Calculator-style percent: 200 + 10% -> 200 + (200 * 10%)
+            let relative_percent = matches!(
+                operator,
+                crate::token::Token::Plus | crate::token::Token::Minus
+            ) && matches!(
+                *right,
+                Unary {
+                    operator: crate::token::Token::Percent,
+                    ..
+                }
+            );
+            let left_span = left.span();
+            let right_span = right.span();
 
-            let left_num = left_val.value.unwrap();
-            let right_num = right_val.value.unwrap();
+            let left_val = evaluate_expression(*left, context, scope)?;
+            let mut right_val = evaluate_expression(*right, context, scope)?;
+
+            if relative_percent {
+                right_val = right_val * left_val.clone();
+            }
 
             match operator {
-                crate::token::Token::Plus => Ok(EvaluationResult {
-                    value: Some(left_num + right_num),
-                }),
-                crate::token::Token::Minus => Ok(EvaluationResult {
-                    value: Some(left_num - right_num),
-                }),
-                crate::token::Token::Multiply => Ok(EvaluationResult {
-                    value: Some(left_num * right_num),
-                }),
+                crate::token::Token::Plus => Ok(left_val + right_val),
+                crate::token::Token::Minus => Ok(left_val - right_val),
+                crate::token::Token::Multiply => Ok(left_val * right_val),
                 crate::token::Token::Divide => {
-                    if right_num == 0.0 {
-                        Err("Division by zero".to_string())
+                    if right_val.is_zero() {
+                        Err(CalcError::DivisionByZero { span: right_span })
                     } else {
-                        Ok(EvaluationResult {
-                            value: Some(left_num / right_num),
-                        })
+                        Ok(left_val / right_val)
                     }
                 }
-                _ => Err("Unsupported operator".to_string()),
+                crate::token::Token::Mod => {
+                    if right_val.is_zero() {
+                        Err(CalcError::DivisionByZero { span: right_span })
+                    } else {
+                        Ok(left_val.modulo(right_val))
+                    }
+                }
+                crate::token::Token::Caret => calc_power(left_val, right_val, span),
+                crate::token::Token::BitAnd
+                | crate::token::Token::BitOr
+                | crate::token::Token::Xor
+                | crate::token::Token::ShiftLeft
+                | crate::token::Token::ShiftRight
+                | crate::token::Token::RotateLeft
+                | crate::token::Token::RotateRight => calc_bitwise(
+                    &operator,
+                    (left_val, left_span),
+                    (right_val, right_span),
+                    span,
+                    context.word(),
+                ),
+                _ => Err(CalcError::UnexpectedToken { span }),
             }
         }
-        Prefix { operator, right } => {
-            let right_val = evaluate_expression(*right)?;
-
-            let right_num = right_val.value.unwrap();
+        Prefix {
+            operator,
+            right,
+            span,
+        } => {
+            let right_span = right.span();
+            let right_val = evaluate_expression(*right, context, scope)?;
 
             match operator {
-                crate::token::Token::Minus => Ok(EvaluationResult {
-                    value: Some(-right_num),
-                }),
-                _ => Err("Unsupported operator".to_string()),
+                crate::token::Token::Minus => Ok(-right_val),
+                crate::token::Token::Not => match right_val.to_integer() {
+                    Some(n) => Ok(Value::Integer(!n)),
+                    None => Err(CalcError::NotAnInteger { span: right_span }),
+                },
+                _ => Err(CalcError::UnexpectedToken { span }),
             }
         }
         Unary {
             operator,
             expression,
+            span,
         } => {
-            let expr_val = evaluate_expression(*expression)?;
-
-            let expr_num = expr_val.value;
+            let expr_val = evaluate_expression(*expression, context, scope)?;
 
             match operator {
-                crate::token::Token::Exclamation => match calc_factorial(expr_num) {
-                    Ok(result) => Ok(EvaluationResult {
-                        value: Some(result),
-                    }),
-                    Err(_) => Err("Failed to compute factorial".to_string()),
-                },
-                _ => Err("Unsupported operator".to_string()),
+                crate::token::Token::Exclamation => {
+                    if let Some(n) = expr_val.to_integer()
+                        && let Some(result) = value::integer_factorial(&n)
+                    {
+                        return Ok(Value::Integer(result));
+                    }
+
+                    match calc_factorial(Some(expr_val.to_f64())) {
+                        Ok(result) if result.is_infinite() => Err(CalcError::Overflow { span }),
+                        Ok(result) => Ok(Value::Float(result)),
+                        Err(_) => Err(CalcError::DomainError { span }),
+                    }
+                }
+                crate::token::Token::Percent => Ok(expr_val / Value::from(100)),
+                _ => Err(CalcError::UnexpectedToken { span }),
             }
         }
+        Call { name, args, span } => {
+            let Some(builtin) = functions::lookup(&name) else {
+                return match scope.environment.function(&name) {
+                    Some(function) => call_function(function, name, args, context, scope, span),
+                    None => Err(CalcError::UnknownIdentifier { name, span }),
+                };
+            };
+
+            if !builtin.accepts(args.len()) {
+                return Err(CalcError::ArgumentCount {
+                    name,
+                    found: args.len(),
+                    span,
+                });
+            }
+
+            let mut values = Vec::with_capacity(args.len());
+            for arg in args {
+                values.push(evaluate_expression(arg, context, scope)?);
+            }
+
+            // Integer arguments give an exact result where the function has one, e.g. gcd
+            let integers: Option<Vec<_>> = values.iter().map(Value::to_integer).collect();
+            if let (Some(exact), Some(integers)) = (builtin.exact, integers) {
+                return Ok(Value::Integer(exact(&integers)));
+            }
+
+            let values: Vec<f64> = values.iter().map(Value::to_f64).collect();
+            Ok(Value::Float(calc_function(
+                builtin, &values, context, span,
+            )?))
+        }
+        // Assignments and definitions are only valid at the top of an expression
+        Assign { span, .. } | Define { span, .. } => Err(CalcError::InvalidAssignment { span }),
     }
 }
 
+/// Evaluates the body of a user-defined function with its parameters bound to args.
+fn call_function(
+    function: &Function,
+    name: String,
+    args: Vec<crate::ast::Expression>,
+    context: &EvaluationContext,
+    scope: &Scope,
+    span: Span,
+) -> Result<Value, CalcError> {
+    if args.len() != function.params().len() {
+        return Err(CalcError::ArgumentCount {
+            name,
+            found: args.len(),
+            span,
+        });
+    }
+
+    if scope.depth >= MAX_CALL_DEPTH {
+        return Err(CalcError::RecursionLimit { span });
+    }
+
+    let mut arguments = Vec::with_capacity(args.len());
+    for (param, arg) in function.params().iter().zip(args) {
+        arguments.push((param.clone(), evaluate_expression(arg, context, scope)?));
+    }
+
+    let inner = Scope {
+        environment: scope.environment,
+        arguments,
+        depth: scope.depth + 1,
+        overflowed: scope.overflowed,
+    };
+
+    evaluate_expression(function.body().clone(), context, &inner)
+}
+
+/// Applies a built-in function, rejecting arguments outside its domain and results too large to
+/// represent.
+fn calc_function(
+    builtin: &functions::Builtin,
+    args: &[f64],
+    context: &EvaluationContext,
+    span: Span,
+) -> Result<f64, CalcError> {
+    if !(builtin.domain)(args) {
+        return Err(CalcError::DomainError { span });
+    }
+
+    let unit = context.angle_unit;
+    let result = match &builtin.angle {
+        Angle::None => (builtin.apply)(args),
+        Angle::Argument(quarter_turns) => {
+            // Whole quarter turns have exact results, so sin(180°) is 0 rather than 1.2e-16
+            let quarters = unit.to_turns(args[0]) * 4.0;
+            if quarters.fract() == 0.0 {
+                return quarter_turns[quarters.rem_euclid(4.0) as usize]
+                    .ok_or(CalcError::DomainError { span });
+            }
+
+            (builtin.apply)(&[unit.to_radians(args[0])])
+        }
+        Angle::Result => unit.radians_to_unit((builtin.apply)(args)),
+    };
+
+    if result.is_nan() {
+        Err(CalcError::DomainError { span })
+    } else if result.is_infinite() {
+        Err(CalcError::Overflow { span })
+    } else {
+        Ok(result)
+    }
+}
+
+/// Raises base to the power of exponent, rejecting results that are undefined or too large to
+/// represent.
+fn calc_power(base: Value, exponent: Value, span: Span) -> Result<Value, CalcError> {
+    if base.is_zero() && exponent.is_negative() {
+        return Err(CalcError::DivisionByZero { span });
+    }
+
+    match base.pow(exponent) {
+        // e.g. a negative base with a fractional exponent has no real result
+        Value::Float(result) if result.is_nan() => Err(CalcError::DomainError { span }),
+        Value::Float(result) if result.is_infinite() => Err(CalcError::Overflow { span }),
+        result => Ok(result),
+    }
+}
+
+/// Applies a bitwise, shift or rotate operator to a pair of operands and their spans, rejecting
+/// operands that are not integers. Rotation works on the bits of word.
+fn calc_bitwise(
+    operator: &crate::token::Token,
+    (left, left_span): (Value, Span),
+    (right, right_span): (Value, Span),
+    span: Span,
+    word: Option<Word>,
+) -> Result<Value, CalcError> {
+    let a = left
+        .to_integer()
+        .ok_or(CalcError::NotAnInteger { span: left_span })?;
+    let b = right
+        .to_integer()
+        .ok_or(CalcError::NotAnInteger { span: right_span })?;
+
+    let result = match operator {
+        crate::token::Token::BitAnd => a & b,
+        crate::token::Token::BitOr => a | b,
+        crate::token::Token::Xor => a ^ b,
+        _ => {
+            // The amount of a shift or rotation is a number of bits
+            if b.is_negative() {
+                return Err(CalcError::DomainError { span: right_span });
+            }
+            let amount = b.to_u64().unwrap_or(u64::MAX);
+
+            match operator {
+                crate::token::Token::ShiftLeft if a.is_zero() => a,
+                // Every bit is shifted out of the word
+                crate::token::Token::ShiftLeft
+                    if word.is_some_and(|word| amount >= u64::from(word.bits)) =>
+                {
+                    BigInt::zero()
+                }
+                crate::token::Token::ShiftLeft => {
+                    if a.bits().saturating_add(amount) > value::MAX_INTEGER_BITS {
+                        return Err(CalcError::Overflow { span });
+                    }
+                    a << amount
+                }
+                // Shifting right rounds towards negative infinity, so every bit of -1 is set
+                crate::token::Token::ShiftRight if amount >= a.bits() => {
+                    BigInt::from(if a.is_negative() { -1 } else { 0 })
+                }
+                crate::token::Token::ShiftRight => a >> amount,
+                _ => {
+                    // Rotation works on the two's complement bits of the word
+                    let bits = word.map_or(ROTATE_BITS, |word| word.bits);
+                    let unsigned = Word {
+                        bits,
+                        signed: false,
+                    };
+                    let bits = u64::from(bits);
+                    let amount = match operator {
+                        crate::token::Token::RotateLeft => amount % bits,
+                        _ => (bits - amount % bits) % bits,
+                    };
+
+                    let n = unsigned.to_unsigned(&a);
+                    unsigned.to_unsigned(&((&n << amount) | (n >> (bits - amount))))
+                }
+            }
+        }
+    };
+
+    Ok(Value::Integer(result))
+}
+
 /// Computes the factorial of a non-negative integer n.
 fn calc_factorial(n: Option<f64>) -> Result<f64, ()> {
     if n.is_none() {
@@ -143,13 +603,17 @@ fn calc_factorial(n: Option<f64>) -> Result<f64, ()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::{AngleUnit, Arithmetic, Rounding};
+    use crate::locale::Locale;
+
+    const FACTORIAL_171: &str = "1241018070217667823424840524103103992616605577501693185388951803611996075221691752992751978120487585576464959501670387052809889858690710767331242032218484364310473577889968548278290754541561964852153468318044293239598173696899657235903947616152278558180061176365108428800000000000000000000000000000000000000000";
 
     #[test]
     fn test_evaluate_int_expression() {
-        let result = evaluate("42".to_string());
+        let result = evaluate("42".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), Some(42));
     }
 
@@ -163,10 +627,10 @@ mod tests {
         ];
 
         for i in input {
-            let result = evaluate(i.0);
+            let result = evaluate(i.0, &EvaluationContext::default());
             assert!(result.is_ok());
             let eval_result = result.unwrap();
-            assert!(is_integer(eval_result.value));
+            assert!(is_integer(eval_result.to_f64()));
             assert_eq!(eval_result.int_value(), Some(i.1));
         }
     }
@@ -181,102 +645,533 @@ mod tests {
         ];
 
         for i in input {
-            let result = evaluate(i.0);
+            let result = evaluate(i.0, &EvaluationContext::default());
             assert!(result.is_ok());
             let eval_result = result.unwrap();
-            assert!(is_integer(eval_result.value));
+            assert!(is_integer(eval_result.to_f64()));
             assert_eq!(eval_result.int_value(), Some(i.1));
         }
     }
 
     #[test]
     fn test_evaluate_division_by_zero() {
-        let result = evaluate("10/0".to_string());
+        let result = evaluate("10/0".to_string(), &EvaluationContext::default());
         assert!(result.is_err());
-        assert_eq!(result.err().unwrap(), "Division by zero".to_string());
+        assert_eq!(
+            result.err().unwrap(),
+            CalcError::DivisionByZero {
+                span: Span::new(3, 4)
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        let input = vec![
+            (
+                "".to_string(),
+                CalcError::UnexpectedEnd {
+                    span: Span::new(0, 0),
+                },
+            ),
+            (
+                "2*(3+4".to_string(),
+                CalcError::UnclosedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "1+(2-2)/(3-3)".to_string(),
+                CalcError::DivisionByZero {
+                    span: Span::new(8, 13),
+                },
+            ),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert_eq!(result.err().unwrap(), i.1);
+        }
     }
 
     #[test]
     fn test_evaluate_nested_expression() {
-        let result = evaluate("2*(3+4)".to_string());
+        let result = evaluate("2*(3+4)".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), Some(14));
     }
 
     #[test]
     fn test_evaluate_factoriacl_expressions() {
-        let result = evaluate("5!".to_string());
+        let result = evaluate("5!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), Some(120));
     }
 
     #[test]
     fn test_evaluate_factoriacl_with_negative_expressions() {
-        let result = evaluate("-5!".to_string());
+        let result = evaluate("-5!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), Some(-120));
     }
 
     #[test]
     fn test_evaluate_factoriacl_float_expressions() {
-        let result = evaluate("2.3!".to_string());
+        let result = evaluate("2.3!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(!is_integer(eval_result.value));
-        assert_eq!(eval_result.value, Some(2.6834373819557666));
+        assert!(!is_integer(eval_result.to_f64()));
+        assert_eq!(eval_result.to_f64(), Some(2.6834373819557666));
     }
 
     #[test]
     fn test_evaluate_factoriacl_negative_float_expressions() {
-        let result = evaluate("-2.3!".to_string());
+        let result = evaluate("-2.3!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(!is_integer(eval_result.value));
-        assert_eq!(eval_result.value, Some(-2.6834373819557666));
+        assert!(!is_integer(eval_result.to_f64()));
+        assert_eq!(eval_result.to_f64(), Some(-2.6834373819557666));
     }
 
     #[test]
     fn test_evaluate_factoriacl_limit_expressions() {
-        let result = evaluate("170!".to_string());
+        let result = evaluate("170!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), None);
-        assert_eq!(eval_result.value(), "7.257415615307994e306");
+        assert_eq!(eval_result.value().len(), 307);
+        assert!(eval_result.value().starts_with("7257415615307998967"));
     }
 
     #[test]
     fn test_evaluate_factoriacl_overflow_expressions() {
-        let result = evaluate("171!".to_string());
+        let result = evaluate("171!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(!is_integer(eval_result.value));
-        assert_eq!(eval_result.value, Some(f64::INFINITY));
+        assert_eq!(eval_result.to_f64(), Some(f64::INFINITY));
+        assert_eq!(eval_result.value(), FACTORIAL_171);
     }
 
     #[test]
     fn test_evaluate_factoriacl_negative_limit_expressions() {
-        let result = evaluate("-170!".to_string());
+        let result = evaluate("-170!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(is_integer(eval_result.value));
+        assert!(is_integer(eval_result.to_f64()));
         assert_eq!(eval_result.int_value(), None);
-        assert_eq!(eval_result.value(), "-7.257415615307994e306");
+        assert!(eval_result.value().starts_with("-7257415615307998967"));
     }
 
     #[test]
     fn test_evaluate_factoriacl_negative_overflow_expressions() {
-        let result = evaluate("-171!".to_string());
+        let result = evaluate("-171!".to_string(), &EvaluationContext::default());
         assert!(result.is_ok());
         let eval_result = result.unwrap();
-        assert!(!is_integer(eval_result.value));
-        assert_eq!(eval_result.value, Some(f64::NEG_INFINITY));
+        assert_eq!(eval_result.to_f64(), Some(f64::NEG_INFINITY));
+        assert_eq!(eval_result.value(), format!("-{}", FACTORIAL_171));
+    }
+
+    #[test]
+    fn test_evaluate_factorial_precedence() {
+        let input = vec![("2*3!", 12), ("2^3!", 64), ("3!^2", 36), ("-3!", -6)];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default());
+            assert_eq!(result.unwrap().int_value(), Some(i.1), "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_factoriacl_beyond_exact_limit() {
+        let input = vec![("10001!", 0, 6), ("sqrt(171^2)!", 0, 12), ("170.5!", 0, 6)];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default());
+            assert_eq!(
+                result.err().unwrap(),
+                CalcError::Overflow {
+                    span: Span::new(i.1, i.2),
+                },
+                "{}",
+                i.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_exact_integers() {
+        let input = vec![
+            ("9223372036854775807+1", "9223372036854775808"),
+            ("2^64", "18446744073709551616"),
+            ("-2^63-1", "-9223372036854775809"),
+            ("10^20/10^18", "100"),
+            ("(2^100+1) mod 7", "3"),
+            ("(25!)/(24!)", "25"),
+            ("gcd(2^70, 6^20)", "1048576"),
+            ("lcm(2^40, 3^20)", "3833759992447475122176"),
+            ("abs(-2^70)", "1180591620717411303424"),
+            ("max(2^65, 2^64)", "36893488147419103232"),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default());
+            assert_eq!(result.unwrap().value(), i.1, "{}", i.0);
+        }
+
+        // An operation without an exact result falls back to floating point
+        let result = evaluate("sqrt(2^64+1)".to_string(), &EvaluationContext::default());
+        assert_eq!(result.unwrap().value(), "4294967296");
+    }
+
+    #[test]
+    fn test_evaluate_exact_fractions() {
+        let input = vec![
+            ("1/3+1/6", "0.5", Some("1/2")),
+            ("0.1+0.2", "0.3", Some("3/10")),
+            ("1.10*3", "3.3", Some("33/10")),
+            ("1/3", "0.3333333333333333", Some("1/3")),
+            ("-2/3", "-0.6666666666666666", Some("-2/3")),
+            ("(2/3)^-2", "2.25", Some("9/4")),
+            ("2^-3", "0.125", Some("1/8")),
+            ("1/3*3", "1", None),
+            ("7.5 mod 2", "1.5", Some("3/2")),
+            ("12.5%", "0.125", Some("1/8")),
+            (
+                "2^64/3",
+                "6148914691236517000",
+                Some("18446744073709551616/3"),
+            ),
+            ("sqrt(1/4)", "0.5", None),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default()).unwrap();
+            assert_eq!(result.value(), i.1, "{}", i.0);
+            assert_eq!(result.fraction().as_deref(), i.2, "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_decimal() {
+        let decimal = |precision, rounding| EvaluationContext {
+            arithmetic: Arithmetic::Decimal {
+                precision,
+                rounding,
+            },
+            ..Default::default()
+        };
+
+        let input = vec![
+            ("1.10*3", 2, Rounding::HalfEven, "3.30"),
+            ("0.1+0.2", 2, Rounding::HalfEven, "0.30"),
+            ("1/3", 2, Rounding::HalfEven, "0.33"),
+            ("2/3", 2, Rounding::HalfEven, "0.67"),
+            ("2/3", 2, Rounding::Truncate, "0.66"),
+            ("1/3", 4, Rounding::HalfEven, "0.3333"),
+            ("1/3*3", 2, Rounding::HalfEven, "1.00"),
+            ("2.345", 2, Rounding::HalfEven, "2.34"),
+            ("2.345", 2, Rounding::HalfUp, "2.35"),
+            ("-2.345", 2, Rounding::HalfUp, "-2.35"),
+            ("0.125", 2, Rounding::HalfEven, "0.12"),
+            ("0.125", 2, Rounding::HalfUp, "0.13"),
+            ("0.125", 2, Rounding::Truncate, "0.12"),
+            ("2^10", 2, Rounding::HalfEven, "1024.00"),
+            ("1.5^2", 3, Rounding::HalfEven, "2.250"),
+            ("5!", 0, Rounding::HalfEven, "120"),
+            ("7.5 mod 2", 1, Rounding::HalfEven, "1.5"),
+            ("200 + 10%", 2, Rounding::HalfEven, "220.00"),
+            ("sqrt(2)", 2, Rounding::HalfEven, "1.41"),
+            ("pi", 4, Rounding::HalfEven, "3.1416"),
+            ("gcd(12, 18)", 2, Rounding::HalfEven, "6.00"),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &decimal(i.1, i.2)).unwrap();
+            assert_eq!(result.value(), i.3, "{} {:?}", i.0, i.2);
+            assert_eq!(result.fraction(), None, "{}", i.0);
+        }
+
+        let mut env = Environment::new();
+        let context = decimal(2, Rounding::HalfEven);
+        env.evaluate("x = 1/3".to_string(), &context).unwrap();
+        assert_eq!(env.get("x").unwrap().value(), "0.33");
+        assert_eq!(
+            env.evaluate("x * 3".to_string(), &context).unwrap().value(),
+            "0.99"
+        );
+
+        let result = evaluate("1/0".to_string(), &context);
+        assert_eq!(
+            result.err().unwrap(),
+            CalcError::DivisionByZero {
+                span: Span::new(2, 3)
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_locale() {
+        let context = |tag| EvaluationContext {
+            locale: Locale::from_language(tag),
+            ..Default::default()
+        };
+
+        let input = vec![
+            ("1.234,5 * 2", "de", "2469"),
+            ("max(1,5; 2,5)", "de", "2.5"),
+            ("1 234 + 1", "fr", "1235"),
+            ("1'000.5 * 2", "de-CH", "2001"),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &context(i.1)).unwrap();
+            assert_eq!(result.value(), i.2, "{} {}", i.0, i.1);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_integer() {
+        let context = |radix| EvaluationContext {
+            arithmetic: Arithmetic::Integer { word: None },
+            radix,
+            ..Default::default()
+        };
+
+        let input = vec![
+            ("7/2", Radix::Decimal, "3"),
+            ("-7/2", Radix::Decimal, "-3"),
+            ("7/2*2", Radix::Decimal, "6"),
+            ("2^-1", Radix::Decimal, "0"),
+            ("sqrt(10)", Radix::Decimal, "3"),
+            ("pi", Radix::Decimal, "3"),
+            ("0xFF + 0b1", Radix::Decimal, "256"),
+            ("FF + 1", Radix::Hexadecimal, "256"),
+            ("10 * 10", Radix::Binary, "4"),
+            ("17 mod 10", Radix::Octal, "7"),
+            ("2^64", Radix::Decimal, "18446744073709551616"),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &context(i.1)).unwrap();
+            assert_eq!(result.value(), i.2, "{} {:?}", i.0, i.1);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_bitwise() {
+        let input = vec![
+            ("12 & 10", "8"),
+            ("12 | 10", "14"),
+            ("12 xor 10", "6"),
+            ("~0", "-1"),
+            ("not 5", "-6"),
+            ("-8 & 0xFF", "248"),
+            ("1 << 4", "16"),
+            ("1 << 100", "1267650600228229401496703205376"),
+            ("0 << 10000000", "0"),
+            ("256 >> 4", "16"),
+            ("-1 >> 4", "-1"),
+            ("-16 >> 2", "-4"),
+            ("5 >> 100", "0"),
+            ("1 << 2 + 1", "8"),
+            ("1 | 2 & 3", "3"),
+            ("1 ror 1", "9223372036854775808"),
+            ("0x8000000000000000 rol 1", "1"),
+            ("1 rol 65", "2"),
+            ("-1 rol 8", "18446744073709551615"),
+            ("sqrt(16) & 0xF", "4"),
+            ("(7/2) & 1", "1"),
+        ];
+
+        let integer = EvaluationContext {
+            arithmetic: Arithmetic::Integer { word: None },
+            ..Default::default()
+        };
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &integer).unwrap();
+            assert_eq!(result.value(), i.1, "{}", i.0);
+        }
+
+        let input = vec![
+            (
+                "2.5 & 1",
+                CalcError::NotAnInteger {
+                    span: Span::new(0, 3),
+                },
+            ),
+            (
+                "1 | 1/2",
+                CalcError::NotAnInteger {
+                    span: Span::new(4, 7),
+                },
+            ),
+            (
+                "~sqrt(2)",
+                CalcError::NotAnInteger {
+                    span: Span::new(1, 8),
+                },
+            ),
+            (
+                "1 << -1",
+                CalcError::DomainError {
+                    span: Span::new(5, 7),
+                },
+            ),
+            (
+                "1 << 10000000",
+                CalcError::Overflow {
+                    span: Span::new(0, 13),
+                },
+            ),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default());
+            assert_eq!(result.err().unwrap(), i.1, "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_word() {
+        let context = |bits, signed| EvaluationContext {
+            arithmetic: Arithmetic::Integer {
+                word: Some(Word { bits, signed }),
+            },
+            ..Default::default()
+        };
+
+        let input = vec![
+            ("127 + 1", 8, true, "-128", true),
+            ("-128 - 1", 8, true, "127", true),
+            ("-(-128)", 8, true, "-128", true),
+            ("255 + 1", 8, false, "0", true),
+            ("0 - 1", 8, false, "255", true),
+            ("16 * 16", 8, false, "0", true),
+            ("2^31", 32, true, "-2147483648", true),
+            ("2^64 - 1", 64, false, "18446744073709551615", true),
+            ("2^63", 64, false, "9223372036854775808", false),
+            ("abs(-128)", 8, true, "-128", true),
+            ("100 + 27", 8, true, "127", false),
+            ("0xFF", 8, true, "-1", false),
+            ("0xFF + 0", 8, true, "-1", false),
+            ("~0", 8, false, "255", false),
+            ("1 << 7", 8, true, "-128", false),
+            ("1 << 8", 8, false, "0", false),
+            ("1 << 10000000", 8, false, "0", false),
+            ("0x80 rol 1", 8, false, "1", false),
+            ("1 ror 1", 8, false, "128", false),
+            ("1 ror 1", 16, false, "32768", false),
+            ("-1 rol 4", 8, true, "-1", false),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &context(i.1, i.2)).unwrap();
+            assert_eq!(result.value(), i.3, "{} {} {}", i.0, i.1, i.2);
+            assert_eq!(result.overflowed(), i.4, "{} {} {}", i.0, i.1, i.2);
+        }
+
+        let mut env = Environment::new();
+        let context = context(8, true);
+        assert!(
+            env.evaluate("x = 127 + 1".to_string(), &context)
+                .unwrap()
+                .overflowed()
+        );
+        let result = env.evaluate("x".to_string(), &context).unwrap();
+        assert_eq!(result.value(), "-128");
+        assert!(!result.overflowed());
+    }
+
+    #[test]
+    fn test_evaluate_to_radix() {
+        let input = vec![
+            ("255", Radix::Hexadecimal, Some("FF")),
+            ("255", Radix::Decimal, Some("255")),
+            ("255", Radix::Octal, Some("377")),
+            ("255", Radix::Binary, Some("11111111")),
+            ("-10", Radix::Hexadecimal, Some("-A")),
+            ("0", Radix::Binary, Some("0")),
+            ("2^64", Radix::Hexadecimal, Some("10000000000000000")),
+            ("1/2", Radix::Hexadecimal, None),
+            ("sqrt(2)", Radix::Hexadecimal, None),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default()).unwrap();
+            assert_eq!(
+                result.to_radix(i.1, None).as_deref(),
+                i.2,
+                "{} {:?}",
+                i.0,
+                i.1
+            );
+        }
+
+        let word = Some(Word {
+            bits: 8,
+            signed: true,
+        });
+        let result = evaluate("-1".to_string(), &EvaluationContext::default()).unwrap();
+        assert_eq!(
+            result.to_radix(Radix::Hexadecimal, word).as_deref(),
+            Some("FF")
+        );
+        assert_eq!(
+            result.to_radix(Radix::Binary, word).as_deref(),
+            Some("11111111")
+        );
+        assert_eq!(result.to_radix(Radix::Decimal, word).as_deref(), Some("-1"));
+
+        let result = evaluate("f(x) = x".to_string(), &EvaluationContext::default()).unwrap();
+        assert_eq!(result.to_radix(Radix::Hexadecimal, None), None);
+    }
+
+    #[test]
+    fn test_evaluate_bits() {
+        let word = |bits, signed| Word { bits, signed };
+
+        let input = vec![
+            ("5", word(8, true), Some("10100000")),
+            ("-1", word(8, true), Some("11111111")),
+            ("-2", word(8, false), Some("01111111")),
+            ("256", word(8, false), Some("00000000")),
+            ("1/2", word(8, true), None),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), &EvaluationContext::default()).unwrap();
+            let bits = result.bits(i.1).map(|bits| {
+                bits.iter()
+                    .map(|&bit| if bit { '1' } else { '0' })
+                    .collect::<String>()
+            });
+            assert_eq!(bits.as_deref(), i.2, "{} {:?}", i.0, i.1);
+        }
+
+        let input = vec![
+            (vec![true, false, true], word(8, true), "5"),
+            (vec![true; 8], word(8, true), "-1"),
+            (vec![true; 8], word(8, false), "255"),
+            (vec![false; 64], word(64, true), "0"),
+        ];
+
+        for i in input {
+            let result = EvaluationResult::from_bits(&i.0, i.1);
+            assert_eq!(result.value(), i.2, "{:?} {:?}", i.0, i.1);
+        }
+
+        let word = word(128, true);
+        let result = evaluate("-2^100".to_string(), &EvaluationContext::default()).unwrap();
+        let bits = result.bits(word).unwrap();
+        assert_eq!(EvaluationResult::from_bits(&bits, word), result);
     }
 
     #[test]
@@ -290,18 +1185,299 @@ mod tests {
     #[test]
     #[ignore = "Not implemented yet"]
     fn test_evaluate_type_mismatch() {
-        let result = evaluate("2+3.5".to_string());
+        let result = evaluate("2+3.5".to_string(), &EvaluationContext::default());
         assert!(result.is_err());
         assert_eq!(
-            result.err().unwrap(),
+            result.err().unwrap().to_string(),
             "Type mismatch: expected integers".to_string()
         );
     }
 
     #[test]
-    fn test_evaluate_unsupported_operator() {
-        let result = evaluate("2^3".to_string());
-        assert!(result.is_err());
-        assert_eq!(result.err().unwrap(), "Unsupported operator".to_string());
+    fn test_evaluate_power_expressions() {
+        let input = vec![
+            ("2^3".to_string(), 8.0),
+            ("2^3^2".to_string(), 512.0),
+            ("(2^3)^2".to_string(), 64.0),
+            ("-2^2".to_string(), -4.0),
+            ("(-2)^2".to_string(), 4.0),
+            ("2^-1".to_string(), 0.5),
+            ("4^0.5".to_string(), 2.0),
+            ("2*3^2".to_string(), 18.0),
+            ("0^0".to_string(), 1.0),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap().to_f64(), Some(i.1));
+        }
+    }
+
+    #[test]
+    fn test_evaluate_power_errors() {
+        let input = vec![
+            (
+                "(-8)^0.5".to_string(),
+                CalcError::DomainError {
+                    span: Span::new(0, 8),
+                },
+            ),
+            (
+                "0^-1".to_string(),
+                CalcError::DivisionByZero {
+                    span: Span::new(0, 4),
+                },
+            ),
+            (
+                "10^400.5".to_string(),
+                CalcError::Overflow {
+                    span: Span::new(0, 8),
+                },
+            ),
+            (
+                "2^2^2^2^2^2".to_string(),
+                CalcError::Overflow {
+                    span: Span::new(0, 11),
+                },
+            ),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert!(result.is_err());
+            assert_eq!(result.err().unwrap(), i.1);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_percent_expressions() {
+        let input = vec![
+            ("50%".to_string(), 0.5),
+            ("200+10%".to_string(), 220.0),
+            ("200-10%".to_string(), 180.0),
+            ("200*10%".to_string(), 20.0),
+            ("200/10%".to_string(), 2000.0),
+            ("50%+1".to_string(), 1.5),
+            ("(200+10)%".to_string(), 2.1),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap().to_f64(), Some(i.1));
+        }
+    }
+
+    #[test]
+    fn test_evaluate_modulo_expressions() {
+        let input = vec![
+            ("7 mod 3".to_string(), 1.0),
+            ("-7 mod 3".to_string(), 2.0),
+            ("7 mod -3".to_string(), -2.0),
+            ("7.5 mod 2".to_string(), 1.5),
+            ("2+7 mod 3".to_string(), 3.0),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap().to_f64(), Some(i.1));
+        }
+
+        let result = evaluate("7 mod 0".to_string(), &EvaluationContext::default());
+        assert_eq!(
+            result.err().unwrap(),
+            CalcError::DivisionByZero {
+                span: Span::new(6, 7)
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_function_calls() {
+        let input = vec![
+            ("sin(0)".to_string(), 0.0),
+            ("cos(0)".to_string(), 1.0),
+            ("atan(1)*4".to_string(), std::f64::consts::PI),
+            ("sqrt(16)".to_string(), 4.0),
+            ("cbrt(-27)".to_string(), -3.0),
+            ("exp(0)".to_string(), 1.0),
+            ("ln(1)".to_string(), 0.0),
+            ("log(1000)".to_string(), 3.0),
+            ("log2(8)".to_string(), 3.0),
+            ("abs(-2.5)".to_string(), 2.5),
+            ("floor(2.7)".to_string(), 2.0),
+            ("ceil(2.2)".to_string(), 3.0),
+            ("round(2.5)".to_string(), 3.0),
+            ("min(3,1,2)".to_string(), 1.0),
+            ("max(3,1+9,2)".to_string(), 10.0),
+            ("gamma(5)".to_string(), 24.0),
+            ("sqrt(9)^2".to_string(), 9.0),
+            ("-sqrt(4)".to_string(), -2.0),
+            ("sqrt(sqrt(16))".to_string(), 2.0),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.clone(), &EvaluationContext::default());
+            assert!(result.is_ok(), "{}", i.0);
+            let value = result.unwrap().to_f64().unwrap();
+            assert!((value - i.1).abs() < 1e-12, "{} = {}", i.0, value);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_function_errors() {
+        let input = vec![
+            (
+                "sqrt(-1)".to_string(),
+                CalcError::DomainError {
+                    span: Span::new(0, 8),
+                },
+            ),
+            (
+                "1+ln(0)".to_string(),
+                CalcError::DomainError {
+                    span: Span::new(2, 7),
+                },
+            ),
+            (
+                "asin(2)".to_string(),
+                CalcError::DomainError {
+                    span: Span::new(0, 7),
+                },
+            ),
+            (
+                "gamma(-1)".to_string(),
+                CalcError::DomainError {
+                    span: Span::new(0, 9),
+                },
+            ),
+            (
+                "exp(1000)".to_string(),
+                CalcError::Overflow {
+                    span: Span::new(0, 9),
+                },
+            ),
+            (
+                "sqrt(1,2)".to_string(),
+                CalcError::ArgumentCount {
+                    name: "sqrt".to_string(),
+                    found: 2,
+                    span: Span::new(0, 9),
+                },
+            ),
+            (
+                "max()".to_string(),
+                CalcError::ArgumentCount {
+                    name: "max".to_string(),
+                    found: 0,
+                    span: Span::new(0, 5),
+                },
+            ),
+            (
+                "foo(1)".to_string(),
+                CalcError::UnknownIdentifier {
+                    name: "foo".to_string(),
+                    span: Span::new(0, 6),
+                },
+            ),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0, &EvaluationContext::default());
+            assert_eq!(result.err().unwrap(), i.1);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_constants() {
+        let input = vec![
+            ("pi".to_string(), std::f64::consts::PI),
+            ("π".to_string(), std::f64::consts::PI),
+            ("2π".to_string(), std::f64::consts::TAU),
+            ("tau/2".to_string(), std::f64::consts::PI),
+            ("τ".to_string(), std::f64::consts::TAU),
+            ("e".to_string(), std::f64::consts::E),
+            ("3e".to_string(), 3.0 * std::f64::consts::E),
+            ("ln(e)".to_string(), 1.0),
+            ("phi^2-phi".to_string(), 1.0),
+            ("φ".to_string(), 1.618033988749895),
+            ("cos(π)".to_string(), -1.0),
+            ("2sin(π/2)".to_string(), 2.0),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.clone(), &EvaluationContext::default());
+            assert!(result.is_ok(), "{}", i.0);
+            let value = result.unwrap().to_f64().unwrap();
+            assert!((value - i.1).abs() < 1e-12, "{} = {}", i.0, value);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_unknown_identifier() {
+        let result = evaluate("2x+1".to_string(), &EvaluationContext::default());
+        assert_eq!(
+            result.err().unwrap(),
+            CalcError::UnknownIdentifier {
+                name: "x".to_string(),
+                span: Span::new(1, 2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_angle_units() {
+        let degrees = EvaluationContext {
+            angle_unit: AngleUnit::Degrees,
+            ..Default::default()
+        };
+        let gradians = EvaluationContext {
+            angle_unit: AngleUnit::Gradians,
+            ..Default::default()
+        };
+        let radians = EvaluationContext::default();
+
+        let input = vec![
+            ("sin(90)", &degrees, 1.0),
+            ("sin(180)", &degrees, 0.0),
+            ("cos(90)", &degrees, 0.0),
+            ("cos(-180)", &degrees, -1.0),
+            ("tan(45)", &degrees, 1.0),
+            ("sin(30)", &degrees, 0.5),
+            ("cos(100)", &gradians, 0.0),
+            ("sin(π)", &radians, 0.0),
+            ("asin(1)", &degrees, 90.0),
+            ("acos(-1)", &degrees, 180.0),
+            ("atan(1)", &gradians, 50.0),
+            ("asin(1)", &radians, std::f64::consts::FRAC_PI_2),
+            ("sinh(0)", &degrees, 0.0),
+        ];
+
+        for i in input {
+            let result = evaluate(i.0.to_string(), i.1);
+            assert!(result.is_ok(), "{}", i.0);
+            let value = result.unwrap().to_f64().unwrap();
+            assert!((value - i.2).abs() < 1e-12, "{} = {}", i.0, value);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_tangent_poles() {
+        let degrees = EvaluationContext {
+            angle_unit: AngleUnit::Degrees,
+            ..Default::default()
+        };
+
+        for input in ["tan(90)", "tan(-270)"] {
+            let result = evaluate(input.to_string(), &degrees);
+            assert_eq!(
+                result.err().unwrap(),
+                CalcError::DomainError {
+                    span: Span::new(0, input.len()),
+                }
+            );
+        }
     }
 }
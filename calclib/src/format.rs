@@ -0,0 +1,395 @@
+use crate::locale::Locale;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Pow, Signed};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// The exponents for which significant figures are written out in full rather than in scientific
+/// notation.
+const POSITIONAL_EXPONENTS: Range<i64> = -5..15;
+
+/// How a number is written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Notation {
+    /// Exact results in full and floating-point results in their shortest form.
+    #[default]
+    Auto,
+    /// A fixed number of decimal places: 3.14159 -> 3.14.
+    Fixed,
+    /// A number of significant figures: 1234.5 -> 1230 to 3 figures.
+    Significant,
+    /// A mantissa between 1 and 10 and a power of ten: 1234.5 -> 1.2345e3.
+    Scientific,
+    /// A mantissa between 1 and 1000 and a power of ten that is a multiple of 3:
+    /// 12345 -> 12.345e3.
+    Engineering,
+}
+
+impl Notation {
+    pub const ALL: [Notation; 5] = [
+        Notation::Auto,
+        Notation::Fixed,
+        Notation::Significant,
+        Notation::Scientific,
+        Notation::Engineering,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Notation::Auto => "auto",
+            Notation::Fixed => "fixed",
+            Notation::Significant => "significant",
+            Notation::Scientific => "scientific",
+            Notation::Engineering => "engineering",
+        }
+    }
+
+    /// The notation after this one in `ALL`, wrapping around to the first.
+    pub fn next(self) -> Notation {
+        let i = Notation::ALL.iter().position(|n| *n == self).unwrap_or(0);
+        Notation::ALL[(i + 1) % Notation::ALL.len()]
+    }
+}
+
+impl FromStr for Notation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Notation::Auto),
+            "fixed" => Ok(Notation::Fixed),
+            "significant" => Ok(Notation::Significant),
+            "scientific" => Ok(Notation::Scientific),
+            "engineering" => Ok(Notation::Engineering),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Options that control how a result is written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Format {
+    pub notation: Notation,
+    /// The decimal places of fixed notation.
+    pub decimals: usize,
+    /// The most significant figures of significant, scientific and engineering notation, which
+    /// drop trailing zeros.
+    pub significant: usize,
+    /// Separate the digits before the decimal point into groups of three.
+    pub grouping: bool,
+    pub group_separator: char,
+    pub decimal_separator: char,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            notation: Notation::Auto,
+            decimals: 2,
+            significant: 10,
+            grouping: false,
+            group_separator: ',',
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl Format {
+    /// The default format with the separators of locale.
+    pub fn for_locale(locale: &Locale) -> Self {
+        Self {
+            group_separator: locale.group_separator,
+            decimal_separator: locale.decimal_separator,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn value(&self, value: &Value) -> String {
+        let plain = match self.notation {
+            Notation::Auto => value.to_string(),
+            Notation::Fixed => fixed(value, self.decimals),
+            notation => match significant_digits(value, self.significant.max(1)) {
+                Some((negative, digits, exponent)) => {
+                    let sign = if negative { "-" } else { "" };
+                    let body = match notation {
+                        Notation::Significant if POSITIONAL_EXPONENTS.contains(&exponent) => {
+                            positional(&digits, exponent)
+                        }
+                        Notation::Engineering => {
+                            exponential(&digits, exponent, exponent.div_euclid(3) * 3)
+                        }
+                        _ => exponential(&digits, exponent, exponent),
+                    };
+                    format!("{}{}", sign, body)
+                }
+                // Infinity and NaN have no digits
+                None => value.to_string(),
+            },
+        };
+
+        self.localize(&plain)
+    }
+
+    /// Applies the separators to a number written with a `.` decimal point and no grouping.
+    fn localize(&self, plain: &str) -> String {
+        let (mantissa, exponent) = plain.split_at(plain.find('e').unwrap_or(plain.len()));
+        let (sign, mantissa) = match mantissa.strip_prefix('-') {
+            Some(mantissa) => ("-", mantissa),
+            None => ("", mantissa),
+        };
+        let (whole, fraction) = match mantissa.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (mantissa, None),
+        };
+
+        let mut localized = String::from(sign);
+        if self.grouping && whole.bytes().all(|b| b.is_ascii_digit()) {
+            for (i, digit) in whole.chars().enumerate() {
+                if i > 0 && (whole.len() - i) % 3 == 0 {
+                    localized.push(self.group_separator);
+                }
+                localized.push(digit);
+            }
+        } else {
+            localized.push_str(whole);
+        }
+
+        if let Some(fraction) = fraction {
+            localized.push(self.decimal_separator);
+            localized.push_str(fraction);
+        }
+        localized.push_str(exponent);
+
+        localized
+    }
+}
+
+/// Writes value rounded half away from zero to a number of decimal places.
+fn fixed(value: &Value, decimals: usize) -> String {
+    let Some(r) = value.to_exact() else {
+        let written = format!("{:.*}", decimals, value.to_f64());
+
+        // A small negative number rounds to "-0.00"
+        return match written.strip_prefix('-') {
+            Some(zero) if zero.bytes().all(|b| b == b'0' || b == b'.') => zero.to_string(),
+            _ => written,
+        };
+    };
+
+    let scaled = (r * power_of_ten(decimals as i64)).round().to_integer();
+    let digits = format!("{:0>width$}", scaled.magnitude(), width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let sign = if scaled.is_negative() { "-" } else { "" };
+
+    if decimals == 0 {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// The sign of value, its digits rounded to a number of significant figures without trailing
+/// zeros, and the power of ten of the first digit: 1234.5 to 3 figures is (false, "123", 3).
+/// `None` if value is infinite or NaN.
+fn significant_digits(value: &Value, figures: usize) -> Option<(bool, String, i64)> {
+    if value.is_zero() {
+        return Some((false, "0".to_string(), 0));
+    }
+
+    let (negative, mut digits, exponent) = match value.to_exact() {
+        Some(r) => {
+            let negative = r.is_negative();
+            let r = r.abs();
+
+            // The exponent is the difference in the number of digits of the numerator and the
+            // denominator, or one less
+            let mut exponent = digit_count(r.numer()) - digit_count(r.denom());
+            if r < power_of_ten(exponent) {
+                exponent -= 1;
+            }
+
+            let mut scaled = (r / power_of_ten(exponent - (figures as i64 - 1)))
+                .round()
+                .to_integer();
+
+            // Rounding may carry into another digit: 9.996 -> 10.00
+            if digit_count(&scaled) > figures as i64 {
+                scaled /= 10;
+                exponent += 1;
+            }
+
+            (negative, scaled.to_string(), exponent)
+        }
+        None => {
+            let f = value.to_f64();
+            if !f.is_finite() {
+                return None;
+            }
+
+            let written = format!("{:.*e}", figures - 1, f.abs());
+            let (mantissa, exponent) = written.split_once('e')?;
+            (f < 0.0, mantissa.replace('.', ""), exponent.parse().ok()?)
+        }
+    };
+
+    let len = digits.trim_end_matches('0').len().max(1);
+    digits.truncate(len);
+
+    Some((negative, digits, exponent))
+}
+
+/// Writes digits with a decimal point, where exponent is the power of ten of the first digit.
+fn positional(digits: &str, exponent: i64) -> String {
+    let len = digits.len() as i64;
+
+    if exponent < 0 {
+        format!("0.{}{}", "0".repeat((-exponent - 1) as usize), digits)
+    } else if exponent + 1 >= len {
+        format!("{}{}", digits, "0".repeat((exponent + 1 - len) as usize))
+    } else {
+        let (whole, fraction) = digits.split_at(exponent as usize + 1);
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// Writes digits as a mantissa and the power of ten shown, where exponent is the power of ten of
+/// the first digit.
+fn exponential(digits: &str, exponent: i64, shown: i64) -> String {
+    format!("{}e{}", positional(digits, exponent - shown), shown)
+}
+
+fn digit_count(n: &BigInt) -> i64 {
+    n.magnitude().to_string().len() as i64
+}
+
+fn power_of_ten(exponent: i64) -> BigRational {
+    let power = BigRational::from_integer(BigInt::from(10).pow(exponent.unsigned_abs()));
+
+    if exponent < 0 { power.recip() } else { power }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::EvaluationContext;
+    use crate::evaluator::evaluate;
+
+    fn format(input: &str, format: &Format) -> String {
+        evaluate(input.to_string(), &EvaluationContext::default())
+            .unwrap()
+            .format(format)
+    }
+
+    #[test]
+    fn test_format_notations() {
+        let inputs = vec![
+            ("1234.5678", Notation::Auto, "1234.5678"),
+            ("1234.5678", Notation::Fixed, "1234.57"),
+            ("1/3", Notation::Fixed, "0.33"),
+            ("2/3", Notation::Fixed, "0.67"),
+            ("-0.005", Notation::Fixed, "-0.01"),
+            ("-0.001", Notation::Fixed, "0.00"),
+            ("-sqrt(0.000001)", Notation::Fixed, "0.00"),
+            ("sqrt(2)", Notation::Fixed, "1.41"),
+            ("42", Notation::Fixed, "42.00"),
+            ("1234.5678", Notation::Significant, "1234.5678"),
+            ("1/3", Notation::Significant, "0.3333333333"),
+            ("2/3", Notation::Significant, "0.6666666667"),
+            ("0.00012", Notation::Significant, "0.00012"),
+            ("2^64", Notation::Significant, "1.844674407e19"),
+            ("sqrt(2)", Notation::Significant, "1.414213562"),
+            ("1/3", Notation::Scientific, "3.333333333e-1"),
+            ("1234.5", Notation::Scientific, "1.2345e3"),
+            ("-1234.5", Notation::Scientific, "-1.2345e3"),
+            ("0", Notation::Scientific, "0e0"),
+            ("9.9999999999", Notation::Scientific, "1e1"),
+            ("171!", Notation::Scientific, "1.24101807e309"),
+            ("sqrt(4) * 10^300", Notation::Scientific, "2e300"),
+            ("12345", Notation::Engineering, "12.345e3"),
+            ("123456", Notation::Engineering, "123.456e3"),
+            ("0.0012", Notation::Engineering, "1.2e-3"),
+            ("0.00012", Notation::Engineering, "120e-6"),
+            ("sqrt(2)*1000", Notation::Engineering, "1.414213562e3"),
+        ];
+
+        for i in inputs {
+            let options = Format {
+                notation: i.1,
+                ..Default::default()
+            };
+            assert_eq!(format(i.0, &options), i.2, "{} {:?}", i.0, i.1);
+        }
+    }
+
+    #[test]
+    fn test_format_precision() {
+        let inputs = vec![
+            ("pi", Notation::Fixed, 4, "3.1416"),
+            ("pi", Notation::Fixed, 0, "3"),
+            ("2.5", Notation::Fixed, 0, "3"),
+            ("1234.5", Notation::Significant, 3, "1230"),
+            ("0.0012345", Notation::Significant, 2, "0.0012"),
+            ("999.9", Notation::Significant, 3, "1000"),
+            ("1234.5", Notation::Scientific, 2, "1.2e3"),
+            ("12345", Notation::Engineering, 1, "10e3"),
+            ("pi", Notation::Scientific, 0, "3e0"),
+        ];
+
+        for i in inputs {
+            let options = Format {
+                notation: i.1,
+                decimals: i.2,
+                significant: i.2,
+                ..Default::default()
+            };
+            assert_eq!(format(i.0, &options), i.3, "{} {:?} {}", i.0, i.1, i.2);
+        }
+    }
+
+    #[test]
+    fn test_format_separators() {
+        let grouped = Format {
+            grouping: true,
+            ..Default::default()
+        };
+        let german = Format {
+            notation: Notation::Fixed,
+            grouping: true,
+            group_separator: '.',
+            decimal_separator: ',',
+            ..Default::default()
+        };
+        let french = Format {
+            grouping: true,
+            ..Format::for_locale(&Locale::from_language("fr"))
+        };
+
+        let inputs = vec![
+            ("1234567", &grouped, "1,234,567"),
+            ("-1234567.891", &grouped, "-1,234,567.891"),
+            ("123", &grouped, "123"),
+            ("1000", &grouped, "1,000"),
+            ("sqrt(4) * 10^300", &grouped, "2e300"),
+            ("1234567.891", &german, "1.234.567,89"),
+            ("0.5", &german, "0,50"),
+            ("1234567.891", &french, "1 234 567,891"),
+        ];
+
+        for i in inputs {
+            assert_eq!(format(i.0, i.1), i.2, "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_notation_strings() {
+        for notation in Notation::ALL {
+            assert_eq!(notation.as_str().parse(), Ok(notation));
+        }
+
+        assert_eq!("bogus".parse::<Notation>(), Err(()));
+        assert_eq!(Notation::Auto.next(), Notation::Fixed);
+        assert_eq!(Notation::Engineering.next(), Notation::Auto);
+    }
+}
@@ -0,0 +1,389 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, Zero};
+use statrs::function::gamma::gamma;
+
+/// How a function relates to the angle unit of the evaluation.
+pub(crate) enum Angle {
+    /// Neither the argument nor the result is an angle.
+    None,
+    /// The argument is an angle. Holds the exact result at each quarter turn starting from zero,
+    /// or `None` where the function has a pole.
+    Argument([Option<f64>; 4]),
+    /// The result is an angle.
+    Result,
+}
+
+/// A function that can be called by name in an expression.
+pub(crate) struct Builtin {
+    pub(crate) name: &'static str,
+    /// The exact number of arguments, or `None` for one or more.
+    pub(crate) arity: Option<usize>,
+    pub(crate) angle: Angle,
+    /// Whether the arguments lie inside the function's domain.
+    pub(crate) domain: fn(&[f64]) -> bool,
+    pub(crate) apply: fn(&[f64]) -> f64,
+    /// Computes the result exactly when every argument is an integer.
+    pub(crate) exact: Option<fn(&[BigInt]) -> BigInt>,
+}
+
+fn any(_: &[f64]) -> bool {
+    true
+}
+
+fn positive(args: &[f64]) -> bool {
+    args[0] > 0.0
+}
+
+fn unit_interval(args: &[f64]) -> bool {
+    (-1.0..=1.0).contains(&args[0])
+}
+
+fn integers(args: &[f64]) -> bool {
+    args.iter().all(|arg| arg.fract() == 0.0)
+}
+
+fn float_gcd(a: f64, b: f64) -> f64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+const BUILTINS: &[Builtin] = &[
+    // Trigonometric
+    Builtin {
+        name: "sin",
+        arity: Some(1),
+        angle: Angle::Argument([Some(0.0), Some(1.0), Some(0.0), Some(-1.0)]),
+        domain: any,
+        apply: |args| args[0].sin(),
+        exact: None,
+    },
+    Builtin {
+        name: "cos",
+        arity: Some(1),
+        angle: Angle::Argument([Some(1.0), Some(0.0), Some(-1.0), Some(0.0)]),
+        domain: any,
+        apply: |args| args[0].cos(),
+        exact: None,
+    },
+    Builtin {
+        name: "tan",
+        arity: Some(1),
+        angle: Angle::Argument([Some(0.0), None, Some(0.0), None]),
+        domain: any,
+        apply: |args| args[0].tan(),
+        exact: None,
+    },
+    Builtin {
+        name: "asin",
+        arity: Some(1),
+        angle: Angle::Result,
+        domain: unit_interval,
+        apply: |args| args[0].asin(),
+        exact: None,
+    },
+    Builtin {
+        name: "acos",
+        arity: Some(1),
+        angle: Angle::Result,
+        domain: unit_interval,
+        apply: |args| args[0].acos(),
+        exact: None,
+    },
+    Builtin {
+        name: "atan",
+        arity: Some(1),
+        angle: Angle::Result,
+        domain: any,
+        apply: |args| args[0].atan(),
+        exact: None,
+    },
+    // Hyperbolic
+    Builtin {
+        name: "sinh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].sinh(),
+        exact: None,
+    },
+    Builtin {
+        name: "cosh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].cosh(),
+        exact: None,
+    },
+    Builtin {
+        name: "tanh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].tanh(),
+        exact: None,
+    },
+    Builtin {
+        name: "asinh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].asinh(),
+        exact: None,
+    },
+    Builtin {
+        name: "acosh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: |args| args[0] >= 1.0,
+        apply: |args| args[0].acosh(),
+        exact: None,
+    },
+    Builtin {
+        name: "atanh",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: |args| args[0] > -1.0 && args[0] < 1.0,
+        apply: |args| args[0].atanh(),
+        exact: None,
+    },
+    // Roots, exponentials and logarithms
+    Builtin {
+        name: "sqrt",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: |args| args[0] >= 0.0,
+        apply: |args| args[0].sqrt(),
+        exact: None,
+    },
+    Builtin {
+        name: "cbrt",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].cbrt(),
+        exact: None,
+    },
+    Builtin {
+        name: "exp",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].exp(),
+        exact: None,
+    },
+    Builtin {
+        name: "ln",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: positive,
+        apply: |args| args[0].ln(),
+        exact: None,
+    },
+    Builtin {
+        name: "log",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: positive,
+        apply: |args| args[0].log10(),
+        exact: None,
+    },
+    Builtin {
+        name: "log10",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: positive,
+        apply: |args| args[0].log10(),
+        exact: None,
+    },
+    Builtin {
+        name: "log2",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: positive,
+        apply: |args| args[0].log2(),
+        exact: None,
+    },
+    // Rounding and comparison
+    Builtin {
+        name: "abs",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].abs(),
+        exact: Some(|args| args[0].abs()),
+    },
+    Builtin {
+        name: "floor",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].floor(),
+        exact: Some(|args| args[0].clone()),
+    },
+    Builtin {
+        name: "ceil",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].ceil(),
+        exact: Some(|args| args[0].clone()),
+    },
+    Builtin {
+        name: "round",
+        arity: Some(1),
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args[0].round(),
+        exact: Some(|args| args[0].clone()),
+    },
+    Builtin {
+        name: "min",
+        arity: None,
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args.iter().copied().fold(f64::INFINITY, f64::min),
+        exact: Some(|args| args.iter().min().unwrap().clone()),
+    },
+    Builtin {
+        name: "max",
+        arity: None,
+        angle: Angle::None,
+        domain: any,
+        apply: |args| args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        exact: Some(|args| args.iter().max().unwrap().clone()),
+    },
+    // Integer functions
+    Builtin {
+        name: "gcd",
+        arity: None,
+        angle: Angle::None,
+        domain: integers,
+        apply: |args| args.iter().copied().fold(0.0, float_gcd),
+        exact: Some(|args| args.iter().fold(BigInt::zero(), |acc, n| acc.gcd(n))),
+    },
+    Builtin {
+        name: "lcm",
+        arity: None,
+        angle: Angle::None,
+        domain: integers,
+        apply: |args| {
+            args.iter().copied().fold(1.0, |acc, n| {
+                let gcd = float_gcd(acc, n);
+                if gcd == 0.0 {
+                    0.0
+                } else {
+                    (acc * n / gcd).abs()
+                }
+            })
+        },
+        exact: Some(|args| args.iter().fold(BigInt::from(1), |acc, n| acc.lcm(n).abs())),
+    },
+    // Special functions
+    Builtin {
+        name: "gamma",
+        arity: Some(1),
+        angle: Angle::None,
+        // gamma has poles at zero and the negative integers
+        domain: |args| args[0] > 0.0 || args[0].fract() != 0.0,
+        apply: |args| gamma(args[0]),
+        exact: None,
+    },
+];
+
+/// Looks up a built-in function by name.
+pub(crate) fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+impl Builtin {
+    /// Whether the function accepts count arguments.
+    pub(crate) fn accepts(&self, count: usize) -> bool {
+        match self.arity {
+            Some(arity) => count == arity,
+            None => count > 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert!(lookup("sin").is_some());
+        assert!(lookup("gamma").is_some());
+        assert!(lookup("SIN").is_none());
+        assert!(lookup("foo").is_none());
+    }
+
+    #[test]
+    fn test_accepts() {
+        let inputs = vec![
+            ("sqrt", 0, false),
+            ("sqrt", 1, true),
+            ("sqrt", 2, false),
+            ("max", 0, false),
+            ("max", 1, true),
+            ("max", 5, true),
+        ];
+
+        for i in inputs {
+            assert_eq!(lookup(i.0).unwrap().accepts(i.1), i.2);
+        }
+    }
+
+    #[test]
+    fn test_exact() {
+        let inputs = vec![
+            ("abs", vec![-3], 3),
+            ("min", vec![4, -2, 7], -2),
+            ("max", vec![4, -2, 7], 7),
+            ("gcd", vec![12, 18], 6),
+            ("gcd", vec![-12, 18, 8], 2),
+            ("lcm", vec![4, 6], 12),
+            ("lcm", vec![4, -6, 5], 60),
+            ("lcm", vec![0, 6], 0),
+        ];
+
+        for i in inputs {
+            let args: Vec<BigInt> = i.1.iter().copied().map(BigInt::from).collect();
+            let exact = lookup(i.0).unwrap().exact.unwrap();
+            assert_eq!(exact(&args), BigInt::from(i.2), "{}{:?}", i.0, i.1);
+        }
+
+        assert!(lookup("sqrt").unwrap().exact.is_none());
+    }
+
+    #[test]
+    fn test_domains() {
+        let inputs = vec![
+            ("sqrt", -1.0, false),
+            ("sqrt", 0.0, true),
+            ("ln", 0.0, false),
+            ("asin", 1.5, false),
+            ("acos", -1.0, true),
+            ("acosh", 0.5, false),
+            ("atanh", 1.0, false),
+            ("gamma", -2.0, false),
+            ("gamma", -2.5, true),
+            ("gamma", 0.0, false),
+            ("gcd", 2.5, false),
+            ("gcd", -4.0, true),
+        ];
+
+        for i in inputs {
+            assert_eq!(
+                (lookup(i.0).unwrap().domain)(&[i.1]),
+                i.2,
+                "{}({})",
+                i.0,
+                i.1
+            );
+        }
+    }
+}
@@ -1,101 +1,254 @@
+use crate::context::{EvaluationContext, Radix};
+use crate::error::{CalcError, Span};
+use crate::locale::Locale;
 use crate::token::Token;
-use std::num::ParseFloatError;
+use crate::value::Value;
 
 pub(crate) struct Lexer {
     input: String,
     position: usize,
     read_position: usize,
     ch: Option<char>,
+    locale: Locale,
+    radix: Radix,
 }
 
 impl Lexer {
     pub(crate) fn new(input: String) -> Self {
+        Self::with_context(input, &EvaluationContext::default())
+    }
+
+    /// A lexer that reads numbers in the locale and radix of context.
+    pub(crate) fn with_context(input: String, context: &EvaluationContext) -> Self {
         let mut lexer = Lexer {
             input,
             position: 0,
             read_position: 0,
             ch: None,
+            locale: context.locale,
+            radix: context.radix,
         };
         lexer.read_char();
         lexer
     }
 
-    fn lookup_token(&mut self, ch: char) -> Result<Token, String> {
+    fn lookup_token(&mut self, ch: char) -> Result<Token, CalcError> {
+        let start = self.position;
+
         match ch {
             '(' => Ok(Token::LParen),
             ')' => Ok(Token::RParen),
+            ch if ch == self.locale.argument_separator() => Ok(Token::Comma),
             '+' => Ok(Token::Plus),
             '-' => Ok(Token::Minus),
             '*' => Ok(Token::Multiply),
             '/' => Ok(Token::Divide),
             '×' => Ok(Token::Multiply),
             '÷' => Ok(Token::Divide),
+            '−' => Ok(Token::Minus),
+            // Symbols for constants are read as their names
+            'π' => Ok(Token::Ident("pi".to_string())),
+            'τ' => Ok(Token::Ident("tau".to_string())),
+            'φ' => Ok(Token::Ident("phi".to_string())),
             '^' => Ok(Token::Caret),
             '%' => Ok(Token::Percent),
             '!' => Ok(Token::Exclamation),
-            '.' | '0'..='9' => {
+            '=' => Ok(Token::Assign),
+            '&' => Ok(Token::BitAnd),
+            '|' => Ok(Token::BitOr),
+            '~' => Ok(Token::Not),
+            '<' | '>' if self.peek_chars().next() == Some(ch) => {
+                self.read_char();
+
+                match ch {
+                    '<' => Ok(Token::ShiftLeft),
+                    _ => Ok(Token::ShiftRight),
+                }
+            }
+            ch if ch.is_ascii_digit() || ch == self.locale.decimal_separator => {
                 let num = self.read_number();
 
                 match num {
-                    Ok(value) => Ok(Token::Number(value)),
-                    Err(_) => Err("Failed to parse number".to_string()),
+                    Some(value) => Ok(Token::Number(value)),
+                    None => Err(CalcError::InvalidNumber {
+                        span: Span::new(start, self.read_position),
+                    }),
                 }
             }
-            _ => Err(format!("Unknown type: {}", ch)),
+            'a'..='z' | 'A'..='Z' => {
+                let ident = self.read_identifier();
+
+                // A hexadecimal number may start with a letter: FF
+                if self.radix == Radix::Hexadecimal && ident.chars().all(|c| self.radix.is_digit(c))
+                {
+                    return Value::parse_radix(&ident, 16).map(Token::Number).ok_or(
+                        CalcError::InvalidNumber {
+                            span: Span::new(start, self.read_position),
+                        },
+                    );
+                }
+
+                match ident.as_str() {
+                    "mod" => Ok(Token::Mod),
+                    "xor" => Ok(Token::Xor),
+                    "not" => Ok(Token::Not),
+                    "rol" => Ok(Token::RotateLeft),
+                    "ror" => Ok(Token::RotateRight),
+                    _ => Ok(Token::Ident(ident)),
+                }
+            }
+            _ => Err(CalcError::UnknownCharacter {
+                ch,
+                span: Span::new(start, self.read_position),
+            }),
         }
     }
 
-    pub(crate) fn next_token(&mut self) -> Result<Token, String> {
+    /// Returns the next token along with the span of input it was read from.
+    pub(crate) fn next_token(&mut self) -> Result<(Token, Span), CalcError> {
+        self.skip_whitespace();
+
         if let Some(ch) = self.ch {
-            let token = self.lookup_token(ch);
+            let start = self.position;
+            let token = self.lookup_token(ch)?;
+            let span = Span::new(start, self.read_position);
             self.read_char();
 
-            token
+            Ok((token, span))
         } else {
-            Ok(Token::Eof)
+            let end = self.input.len();
+            Ok((Token::Eof, Span::new(end, end)))
         }
     }
 
-    fn peek_is_digit(&self) -> bool {
+    /// The characters after the current one.
+    fn peek_chars(&self) -> std::str::Chars<'_> {
+        self.input.get(self.read_position..).unwrap_or("").chars()
+    }
+
+    fn peek_is_ident_char(&self) -> bool {
         if self.read_position < self.input.len() {
-            return self.input.as_bytes()[self.read_position].is_ascii_digit();
+            let byte = self.input.as_bytes()[self.read_position];
+            return byte.is_ascii_alphanumeric() || byte == b'_';
         }
 
         false
     }
 
-    fn peek_is_dot(&self) -> bool {
-        if self.read_position < self.input.len() {
-            return self.input.as_bytes()[self.read_position] == b'.';
+    fn skip_whitespace(&mut self) {
+        while self.ch.is_some_and(|ch| ch.is_ascii_whitespace()) {
+            self.read_char();
         }
-
-        false
     }
 
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = None;
-        } else {
-            self.ch = Some(self.input.as_bytes()[self.read_position] as char);
-        }
+        self.ch = self
+            .input
+            .get(self.read_position..)
+            .and_then(|rest| rest.chars().next());
 
         self.position = self.read_position;
-        self.read_position += 1;
+        self.read_position += self.ch.map_or(1, char::len_utf8);
     }
 
-    fn read_number(&mut self) -> Result<f64, ParseFloatError> {
+    /// The radix of a `0x`, `0o` or `0b` prefix at the current character, if a digit follows it.
+    fn peek_prefix(&self) -> Option<u32> {
+        if self.ch != Some('0') {
+            return None;
+        }
+
+        let mut peek = self.peek_chars();
+        let radix = match peek.next()? {
+            'x' => 16,
+            'o' => 8,
+            'b' => 2,
+            _ => return None,
+        };
+
+        peek.next().filter(|c| c.is_digit(radix)).map(|_| radix)
+    }
+
+    /// Reads a literal such as `12`, `2.5` or, where the locale groups digits, `1.234,5` as an
+    /// exact number. Integers may be written in another radix with a prefix, `0xFF`, or in the
+    /// radix of the lexer without one.
+    fn read_number(&mut self) -> Option<Value> {
+        if let Some(radix) = self.peek_prefix() {
+            self.read_char();
+            let position = self.read_position;
+            while self.peek_is_ident_char() {
+                self.read_char();
+            }
+
+            return Value::parse_radix(&self.input[position..self.read_position], radix);
+        }
+
         let position = self.position;
-        while self.ch.is_some() {
-            if self.peek_is_digit() || self.peek_is_dot() {
+        if self.radix != Radix::Decimal {
+            while self
+                .peek_chars()
+                .next()
+                .is_some_and(|c| self.radix.is_digit(c))
+            {
                 self.read_char();
-            } else {
-                break;
+            }
+
+            return Value::parse_radix(
+                &self.input[position..self.read_position],
+                self.radix.value(),
+            );
+        }
+
+        let locale = self.locale;
+        let decimal = locale.decimal_separator;
+        let is_group = |ch: char| locale.reads_groups() && locale.is_group_separator(ch);
+        let mut fraction = false;
+        let mut valid = true;
+
+        loop {
+            let mut peek = self.peek_chars();
+            let (next, after) = (peek.next(), peek.next());
+
+            match next {
+                Some(ch) if ch.is_ascii_digit() => self.read_char(),
+                Some(ch) if ch == decimal => {
+                    fraction = true;
+                    self.read_char();
+                }
+                // A group separator is only part of the number when a digit follows: "1 234" but
+                // not "1 + 2". It must then separate groups of three digits of the whole part, so
+                // that "1.5" in German is not read as 15
+                Some(ch) if is_group(ch) && after.is_some_and(|ch| ch.is_ascii_digit()) => {
+                    self.read_char();
+                    let digits = self
+                        .peek_chars()
+                        .take_while(|ch| ch.is_ascii_digit())
+                        .count();
+                    valid &= !fraction && digits == 3;
+                }
+                _ => break,
             }
         }
 
-        let s = self.input[position..self.position + 1].to_string();
+        if !valid {
+            return None;
+        }
 
-        s.parse::<f64>()
+        let s: String = self.input[position..self.read_position]
+            .chars()
+            .filter(|ch| !is_group(*ch))
+            .map(|ch| if ch == decimal { '.' } else { ch })
+            .collect();
+
+        Value::parse_decimal(&s)
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let position = self.position;
+        while self.peek_is_ident_char() {
+            self.read_char();
+        }
+
+        self.input[position..self.read_position].to_string()
     }
 }
 
@@ -114,15 +267,35 @@ mod tests {
 
         for i in input {
             let mut l = Lexer::new(i.0.to_string());
-            let token = l.next_token().unwrap();
-            let expected_value = i.1 as f64;
-            assert_eq!(token, Token::Number(expected_value));
+            let (token, _) = l.next_token().unwrap();
+            assert_eq!(token, Token::Number(Value::from(i.1)));
+        }
+    }
+
+    #[test]
+    fn test_lexer_exact_literals() {
+        let input = vec![
+            (
+                "123456789012345678901234567890",
+                Token::Number(Value::Integer(
+                    "123456789012345678901234567890".parse().unwrap(),
+                )),
+            ),
+            ("2.5", Token::Number(Value::parse_decimal("2.5").unwrap())),
+            (".5", Token::Number(Value::parse_decimal("0.5").unwrap())),
+            ("3.", Token::Number(Value::from(3))),
+        ];
+
+        for i in input {
+            let mut l = Lexer::new(i.0.to_string());
+            let (token, _) = l.next_token().unwrap();
+            assert_eq!(token, i.1, "{}", i.0);
         }
     }
 
     #[test]
     fn test_lexer_operators() {
-        let input = "+-*/()%^!";
+        let input = "+-*/()%^!=";
         let mut l = Lexer::new(input.to_string());
 
         let expected_tokens = vec![
@@ -135,10 +308,92 @@ mod tests {
             Token::Percent,
             Token::Caret,
             Token::Exclamation,
+            Token::Assign,
         ];
 
         for expected in expected_tokens {
-            let token = l.next_token().unwrap();
+            let (token, _) = l.next_token().unwrap();
+            assert_eq!(token, expected);
+        }
+    }
+
+    #[test]
+    fn test_lexer_bitwise_operators() {
+        let input = "& | ~ << >> xor not rol ror";
+        let mut l = Lexer::new(input.to_string());
+
+        let expected_tokens = vec![
+            (Token::BitAnd, Span::new(0, 1)),
+            (Token::BitOr, Span::new(2, 3)),
+            (Token::Not, Span::new(4, 5)),
+            (Token::ShiftLeft, Span::new(6, 8)),
+            (Token::ShiftRight, Span::new(9, 11)),
+            (Token::Xor, Span::new(12, 15)),
+            (Token::Not, Span::new(16, 19)),
+            (Token::RotateLeft, Span::new(20, 23)),
+            (Token::RotateRight, Span::new(24, 27)),
+            (Token::Eof, Span::new(27, 27)),
+        ];
+
+        for expected in expected_tokens {
+            assert_eq!(l.next_token().unwrap(), expected);
+        }
+
+        let mut l = Lexer::new("1 < 2".to_string());
+        l.next_token().unwrap();
+        assert_eq!(
+            l.next_token().err().unwrap(),
+            CalcError::UnknownCharacter {
+                ch: '<',
+                span: Span::new(2, 3),
+            }
+        );
+    }
+
+    #[test]
+    fn test_lexer_keywords() {
+        let input = "7 mod 3 7mod(3)";
+        let mut l = Lexer::new(input.to_string());
+
+        let expected_tokens = vec![
+            Token::Number(Value::from(7)),
+            Token::Mod,
+            Token::Number(Value::from(3)),
+            Token::Number(Value::from(7)),
+            Token::Mod,
+            Token::LParen,
+            Token::Number(Value::from(3)),
+            Token::RParen,
+            Token::Eof,
+        ];
+
+        for expected in expected_tokens {
+            let (token, _) = l.next_token().unwrap();
+            assert_eq!(token, expected);
+        }
+    }
+
+    #[test]
+    fn test_lexer_identifiers() {
+        let input = "sin(x) log10(2,y_1)";
+        let mut l = Lexer::new(input.to_string());
+
+        let expected_tokens = vec![
+            Token::Ident("sin".to_string()),
+            Token::LParen,
+            Token::Ident("x".to_string()),
+            Token::RParen,
+            Token::Ident("log10".to_string()),
+            Token::LParen,
+            Token::Number(Value::from(2)),
+            Token::Comma,
+            Token::Ident("y_1".to_string()),
+            Token::RParen,
+            Token::Eof,
+        ];
+
+        for expected in expected_tokens {
+            let (token, _) = l.next_token().unwrap();
             assert_eq!(token, expected);
         }
     }
@@ -150,4 +405,266 @@ mod tests {
         let result = l.next_token();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_lexer_spans() {
+        let input = "12 + (3.5)";
+        let mut l = Lexer::new(input.to_string());
+
+        let expected_spans = vec![
+            Span::new(0, 2),
+            Span::new(3, 4),
+            Span::new(5, 6),
+            Span::new(6, 9),
+            Span::new(9, 10),
+            Span::new(10, 10),
+        ];
+
+        for expected in expected_spans {
+            let (_, span) = l.next_token().unwrap();
+            assert_eq!(span, expected);
+        }
+    }
+
+    #[test]
+    fn test_lexer_unicode_symbols() {
+        let input = "2π×τ−φ÷1";
+        let mut l = Lexer::new(input.to_string());
+
+        let expected_tokens = vec![
+            (Token::Number(Value::from(2)), Span::new(0, 1)),
+            (Token::Ident("pi".to_string()), Span::new(1, 3)),
+            (Token::Multiply, Span::new(3, 5)),
+            (Token::Ident("tau".to_string()), Span::new(5, 7)),
+            (Token::Minus, Span::new(7, 10)),
+            (Token::Ident("phi".to_string()), Span::new(10, 12)),
+            (Token::Divide, Span::new(12, 14)),
+            (Token::Number(Value::from(1)), Span::new(14, 15)),
+            (Token::Eof, Span::new(15, 15)),
+        ];
+
+        for expected in expected_tokens {
+            let token = l.next_token().unwrap();
+            assert_eq!(token, expected);
+        }
+    }
+
+    #[test]
+    fn test_lexer_locale() {
+        let number = |s: &str| Token::Number(Value::parse_decimal(s).unwrap());
+
+        let input = vec![
+            ("1.234,5", "de", vec![number("1234.5")]),
+            ("2,5", "de", vec![number("2.5")]),
+            ("1.234.567", "de", vec![number("1234567")]),
+            ("1 234,5", "fr", vec![number("1234.5")]),
+            ("1\u{202F}234,5", "fr", vec![number("1234.5")]),
+            ("12\u{A0}345", "fr", vec![number("12345")]),
+            ("1'234.5", "de-CH", vec![number("1234.5")]),
+            ("1 + 2", "fr", vec![number("1"), Token::Plus, number("2")]),
+            (
+                "max(1,5; 2)",
+                "de",
+                vec![
+                    Token::Ident("max".to_string()),
+                    Token::LParen,
+                    number("1.5"),
+                    Token::Comma,
+                    number("2"),
+                    Token::RParen,
+                ],
+            ),
+            (
+                "max(1,234)",
+                "en",
+                vec![
+                    Token::Ident("max".to_string()),
+                    Token::LParen,
+                    number("1"),
+                    Token::Comma,
+                    number("234"),
+                    Token::RParen,
+                ],
+            ),
+        ];
+
+        for i in input {
+            let context = EvaluationContext {
+                locale: Locale::from_language(i.1),
+                ..Default::default()
+            };
+            let mut l = Lexer::with_context(i.0.to_string(), &context);
+            let mut tokens = Vec::new();
+            loop {
+                match l.next_token().unwrap() {
+                    (Token::Eof, _) => break,
+                    (token, _) => tokens.push(token),
+                }
+            }
+            assert_eq!(tokens, i.2, "{} {}", i.0, i.1);
+        }
+
+        let input = vec![
+            ("1.5", "de", Span::new(0, 3)),
+            ("1.2345", "de", Span::new(0, 6)),
+            ("1,5.000", "de", Span::new(0, 7)),
+            ("2 3", "fr", Span::new(0, 3)),
+            ("1+2 34", "fr", Span::new(2, 6)),
+            ("1\u{202F}23", "fr", Span::new(0, 6)),
+        ];
+
+        for i in input {
+            let context = EvaluationContext {
+                locale: Locale::from_language(i.1),
+                ..Default::default()
+            };
+            let mut l = Lexer::with_context(i.0.to_string(), &context);
+            let result = loop {
+                match l.next_token() {
+                    Ok((Token::Eof, _)) => break None,
+                    Ok(_) => continue,
+                    Err(e) => break Some(e),
+                }
+            };
+            assert_eq!(
+                result,
+                Some(CalcError::InvalidNumber { span: i.2 }),
+                "{} {}",
+                i.0,
+                i.1
+            );
+        }
+
+        let mut l = Lexer::new("1;2".to_string());
+        l.next_token().unwrap();
+        assert_eq!(
+            l.next_token().err().unwrap(),
+            CalcError::UnknownCharacter {
+                ch: ';',
+                span: Span::new(1, 2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_lexer_radix() {
+        let number = |n: i64| Token::Number(Value::from(n));
+
+        let input = vec![
+            ("0xFF", Radix::Decimal, vec![number(255)]),
+            ("0xff", Radix::Decimal, vec![number(255)]),
+            ("0o17", Radix::Decimal, vec![number(15)]),
+            ("0b101", Radix::Binary, vec![number(5)]),
+            ("0b101", Radix::Hexadecimal, vec![number(5)]),
+            ("0B1", Radix::Hexadecimal, vec![number(177)]),
+            (
+                "0x",
+                Radix::Decimal,
+                vec![number(0), Token::Ident("x".to_string())],
+            ),
+            ("FF", Radix::Hexadecimal, vec![number(255)]),
+            ("1A", Radix::Hexadecimal, vec![number(26)]),
+            ("10", Radix::Octal, vec![number(8)]),
+            ("10", Radix::Binary, vec![number(2)]),
+            ("FF", Radix::Decimal, vec![Token::Ident("FF".to_string())]),
+            (
+                "abs(A)",
+                Radix::Hexadecimal,
+                vec![
+                    Token::Ident("abs".to_string()),
+                    Token::LParen,
+                    number(10),
+                    Token::RParen,
+                ],
+            ),
+            (
+                "2x",
+                Radix::Hexadecimal,
+                vec![number(2), Token::Ident("x".to_string())],
+            ),
+        ];
+
+        for i in input {
+            let context = EvaluationContext {
+                radix: i.1,
+                ..Default::default()
+            };
+            let mut l = Lexer::with_context(i.0.to_string(), &context);
+            let mut tokens = Vec::new();
+            loop {
+                match l.next_token().unwrap() {
+                    (Token::Eof, _) => break,
+                    (token, _) => tokens.push(token),
+                }
+            }
+            assert_eq!(tokens, i.2, "{} {:?}", i.0, i.1);
+        }
+
+        let input = vec![
+            ("0xFG", Radix::Decimal, Span::new(0, 4)),
+            ("0b102", Radix::Decimal, Span::new(0, 5)),
+            ("1+2", Radix::Binary, Span::new(2, 3)),
+            ("1.5", Radix::Hexadecimal, Span::new(1, 3)),
+        ];
+
+        for i in input {
+            let context = EvaluationContext {
+                radix: i.1,
+                ..Default::default()
+            };
+            let mut l = Lexer::with_context(i.0.to_string(), &context);
+            let result = loop {
+                match l.next_token() {
+                    Ok((Token::Eof, _)) => break None,
+                    Ok(_) => continue,
+                    Err(e) => break Some(e),
+                }
+            };
+            assert_eq!(
+                result,
+                Some(CalcError::InvalidNumber { span: i.2 }),
+                "{} {:?}",
+                i.0,
+                i.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        let input = vec![
+            (
+                "1+@",
+                CalcError::UnknownCharacter {
+                    ch: '@',
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "1+√",
+                CalcError::UnknownCharacter {
+                    ch: '√',
+                    span: Span::new(2, 5),
+                },
+            ),
+            (
+                "1+1.2.3",
+                CalcError::InvalidNumber {
+                    span: Span::new(2, 7),
+                },
+            ),
+        ];
+
+        for (expr, expected) in input {
+            let mut l = Lexer::new(expr.to_string());
+            let result = loop {
+                match l.next_token() {
+                    Ok((Token::Eof, _)) => break None,
+                    Ok(_) => continue,
+                    Err(e) => break Some(e),
+                }
+            };
+            assert_eq!(result, Some(expected));
+        }
+    }
 }
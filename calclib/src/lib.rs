@@ -1,7 +1,15 @@
 mod ast;
+mod constants;
+pub mod context;
+pub mod environment;
+pub mod error;
 pub mod evaluator;
+pub mod format;
+mod functions;
 mod lexer;
-mod parser;
+pub mod locale;
+pub mod parser;
 mod token;
 mod utils;
 pub mod validator;
+mod value;
@@ -0,0 +1,122 @@
+/// The characters a language uses to write numbers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Locale {
+    /// The character between the whole and fractional digits: 2.5 or 2,5.
+    pub decimal_separator: char,
+    /// The character between groups of three digits: 1,234 or 1.234 or 1 234.
+    pub group_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: ',',
+        }
+    }
+}
+
+impl Locale {
+    /// The separators of a language given as a tag such as "de-DE" or "fr_FR.UTF-8", or those of
+    /// English if the language is not known.
+    pub fn from_language(tag: &str) -> Locale {
+        let tag = tag.split('.').next().unwrap_or(tag);
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next().unwrap_or("").to_ascii_lowercase();
+        let region = subtags.next().unwrap_or("").to_ascii_uppercase();
+
+        let (decimal_separator, group_separator) = match (language.as_str(), region.as_str()) {
+            ("de" | "it", "CH" | "LI") => ('.', '\''),
+            ("es", "MX" | "US") => ('.', ','),
+            (
+                "da" | "de" | "el" | "es" | "hr" | "id" | "it" | "nl" | "pt" | "ro" | "sl" | "sr"
+                | "tr" | "vi",
+                _,
+            ) => (',', '.'),
+            (
+                "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+                | "ru" | "sk" | "sv" | "uk",
+                _,
+            ) => (',', ' '),
+            _ => return Locale::default(),
+        };
+
+        Locale {
+            decimal_separator,
+            group_separator,
+        }
+    }
+
+    /// The character between the arguments of a function, which is `;` where the decimal
+    /// separator is a comma: max(1,5; 2).
+    pub fn argument_separator(&self) -> char {
+        if self.decimal_separator == ',' {
+            ';'
+        } else {
+            ','
+        }
+    }
+
+    /// Whether ch separates groups of digits. Where that is a space, the no-break spaces French
+    /// formatting uses are accepted too: 1 234, 1\u{A0}234 or 1\u{202F}234.
+    pub(crate) fn is_group_separator(&self, ch: char) -> bool {
+        ch == self.group_separator
+            || (self.group_separator == ' ' && matches!(ch, '\u{A0}' | '\u{202F}'))
+    }
+
+    /// Whether numbers may be typed with group separators. They may not where the group separator
+    /// also separates arguments, as max(1,234) would be ambiguous.
+    pub(crate) fn reads_groups(&self) -> bool {
+        self.group_separator != self.argument_separator()
+            && self.group_separator != self.decimal_separator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_language() {
+        let inputs = vec![
+            ("en", '.', ','),
+            ("en-US", '.', ','),
+            ("C", '.', ','),
+            ("", '.', ','),
+            ("de-DE", ',', '.'),
+            ("de_AT.UTF-8", ',', '.'),
+            ("de-CH", '.', '\''),
+            ("fr-FR", ',', ' '),
+            ("FR", ',', ' '),
+            ("es", ',', '.'),
+            ("es-MX", '.', ','),
+            ("pt-BR", ',', '.'),
+            ("ja-JP", '.', ','),
+        ];
+
+        for i in inputs {
+            let locale = Locale::from_language(i.0);
+            assert_eq!(locale.decimal_separator, i.1, "{}", i.0);
+            assert_eq!(locale.group_separator, i.2, "{}", i.0);
+        }
+    }
+
+    #[test]
+    fn test_locale_separators() {
+        let english = Locale::default();
+        let german = Locale::from_language("de");
+        let swiss = Locale::from_language("de-CH");
+
+        assert_eq!(english.argument_separator(), ',');
+        assert_eq!(german.argument_separator(), ';');
+        assert_eq!(swiss.argument_separator(), ',');
+
+        assert!(german.is_group_separator('.'));
+        assert!(!german.is_group_separator('\u{A0}'));
+        assert!(Locale::from_language("fr").is_group_separator('\u{202F}'));
+
+        assert!(!english.reads_groups());
+        assert!(german.reads_groups());
+        assert!(swiss.reads_groups());
+    }
+}
@@ -1,14 +1,95 @@
 use crate::{
     ast::Expression,
+    context::EvaluationContext,
+    error::{CalcError, Span},
     lexer::Lexer,
-    token::{LOWEST, PREFIX, Token},
+    token::{LOWEST, MULTIPLY, PREFIX, Token},
 };
 
-pub struct Parser {
+/// How far input is from being an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseStatus {
+    /// The input is a whole expression, though evaluating it may still fail.
+    Complete(Expr),
+    /// The input is the start of an expression, such as `3-` or `(399`, and could be finished by
+    /// one of the expected tokens. Where a closing parenthesis is expected, closing the
+    /// `open_parens` parentheses left open finishes it: `2*(3+(4` -> `2*(3+(4))`.
+    Incomplete {
+        expected: Vec<TokenKind>,
+        open_parens: usize,
+    },
+    /// No text added to the end of the input can make it an expression.
+    Invalid(CalcError),
+}
+
+/// A parsed expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr(Expression);
+
+impl Expr {
+    /// The part of the input the expression was parsed from.
+    pub fn span(&self) -> Span {
+        self.0.span()
+    }
+}
+
+/// The kinds of token that may come next in an incomplete expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Number,
+    /// The name of a variable, constant or function.
+    Name,
+    /// An operator before its operand: `-` or `not`.
+    Prefix,
+    /// An operator after an operand, such as `+`, `^` or `!`.
+    Operator,
+    OpenParen,
+    CloseParen,
+    /// The separator between the arguments of a function, `,` or `;` depending on the locale.
+    Separator,
+}
+
+impl TokenKind {
+    /// The tokens that can start an operand.
+    const OPERAND: [TokenKind; 4] = [
+        TokenKind::Number,
+        TokenKind::Name,
+        TokenKind::Prefix,
+        TokenKind::OpenParen,
+    ];
+}
+
+/// Parses input without evaluating it, telling input that is still being typed from input that
+/// can never be an expression. Empty input is incomplete.
+pub fn parse(input: &str, context: &EvaluationContext) -> ParseStatus {
+    let mut parser = Parser::with_context(context.clone());
+
+    match parser.parse(input.to_string()) {
+        Ok(Some(expression)) => ParseStatus::Complete(Expr(expression)),
+        Ok(None) => ParseStatus::Incomplete {
+            expected: TokenKind::OPERAND.to_vec(),
+            open_parens: 0,
+        },
+        Err(err) if err.is_incomplete() => ParseStatus::Incomplete {
+            expected: parser.expected,
+            open_parens: parser.open_parens,
+        },
+        Err(err) => ParseStatus::Invalid(err),
+    }
+}
+
+pub(crate) struct Parser {
     lexer: Lexer,
-    curr_token: Option<Token>,
-    peek_token: Option<Token>,
+    curr_token: Token,
+    curr_span: Span,
+    peek_token: Token,
+    peek_span: Span,
     found_eof: bool,
+    /// The tokens that could have continued the input when it ended too early.
+    expected: Vec<TokenKind>,
+    /// The number of parentheses opened and not yet closed where parsing stopped.
+    open_parens: usize,
+    context: EvaluationContext,
 }
 
 impl Default for Parser {
@@ -18,151 +99,332 @@ impl Default for Parser {
 }
 impl Parser {
     pub(crate) fn new() -> Self {
+        Self::with_context(EvaluationContext::default())
+    }
+
+    /// A parser for input whose numbers are written in the locale and radix of context.
+    pub(crate) fn with_context(context: EvaluationContext) -> Self {
         Self {
             lexer: Lexer::new("".to_string()),
-            curr_token: None,
-            peek_token: None,
+            curr_token: Token::Eof,
+            curr_span: Span::default(),
+            peek_token: Token::Eof,
+            peek_span: Span::default(),
             found_eof: false,
+            expected: Vec::new(),
+            open_parens: 0,
+            context,
         }
     }
 
-    fn next_token(&mut self) {
-        self.curr_token = self.peek_token;
-        self.peek_token = self.lexer.next_token().ok();
+    fn next_token(&mut self) -> Result<(), CalcError> {
+        self.curr_token = std::mem::replace(&mut self.peek_token, Token::Eof);
+        self.curr_span = self.peek_span;
+        (self.peek_token, self.peek_span) = self.lexer.next_token()?;
+
+        Ok(())
     }
 
-    pub(crate) fn parse(&mut self, input: String) -> Result<Option<Expression>, String> {
-        self.lexer = Lexer::new(input);
+    /// Parses input into an expression tree. Returns `Ok(None)` when the input is empty.
+    pub(crate) fn parse(&mut self, input: String) -> Result<Option<Expression>, CalcError> {
+        self.lexer = Lexer::with_context(input, &self.context);
         self.found_eof = false;
-        self.next_token();
-        self.next_token();
+        self.open_parens = 0;
+        self.next_token()?;
+        self.next_token()?;
 
-        if self.curr_token.is_none() {
+        if self.curr_token == Token::Eof {
             return Ok(None);
         }
 
-        let expression = self.parse_expression(LOWEST);
+        let expression = self.parse_expression(LOWEST)?;
+
+        if self.peek_token == Token::Assign {
+            return self.parse_assignment(expression).map(Some);
+        }
 
         if !self.found_eof {
-            return Ok(None);
+            return Err(self.trailing_token_error());
         }
 
-        if expression.is_none() {
-            return Ok(None);
+        Ok(Some(expression))
+    }
+
+    /// The error for a token left over when parsing stops before the end of the input, because it
+    /// cannot follow a complete expression.
+    fn trailing_token_error(&self) -> CalcError {
+        match self.peek_token {
+            Token::RParen => CalcError::UnbalancedParen {
+                span: self.peek_span,
+            },
+            _ => CalcError::UnexpectedToken {
+                span: self.peek_span,
+            },
+        }
+    }
+
+    /// Parses the right side of `x = 5` or `f(x) = x^2 + 1`, where target is the left side.
+    fn parse_assignment(&mut self, target: Expression) -> Result<Expression, CalcError> {
+        // The parameters of a function definition must be plain names
+        if let Expression::Call { args, .. } = &target
+            && let Some(arg) = args
+                .iter()
+                .find(|arg| !matches!(arg, Expression::Identifier { .. }))
+        {
+            return Err(CalcError::InvalidAssignment { span: arg.span() });
+        }
+
+        self.next_token()?;
+        self.next_token()?;
+        let value = self.parse_expression(LOWEST)?;
+
+        if !self.found_eof {
+            return Err(self.trailing_token_error());
         }
 
-        Ok(expression)
+        match target {
+            Expression::Identifier { name, span } => Ok(Expression::Assign {
+                name,
+                span: span.to(value.span()),
+                value: Box::new(value),
+            }),
+            Expression::Call { name, args, span } => Ok(Expression::Define {
+                name,
+                params: args
+                    .into_iter()
+                    .filter_map(|arg| match arg {
+                        Expression::Identifier { name, .. } => Some(name),
+                        _ => None,
+                    })
+                    .collect(),
+                span: span.to(value.span()),
+                body: Box::new(value),
+            }),
+            _ => Err(CalcError::InvalidAssignment {
+                span: target.span(),
+            }),
+        }
     }
 
-    fn parse_infix(&mut self, left: Option<Expression>) -> Option<Expression> {
+    fn parse_infix(&mut self, left: Expression) -> Result<Expression, CalcError> {
         // Handle implicit multiplication: 5(3-1) -> 5 * (3-1)
-        if self.curr_token == Some(Token::LParen) {
-            self.next_token();
-            let right = self.parse_expression(LOWEST);
-            self.next_token();
+        if self.curr_token == Token::LParen {
+            let right = self.parse_grouped()?;
 
-            if !self.test_current_token(Token::RParen) {
-                return None;
-            }
+            return Ok(Expression::Infix {
+                span: left.span().to(self.curr_span),
+                left: Box::new(left),
+                operator: Token::Multiply,
+                right: Box::new(right),
+            });
+        }
+
+        // Handle implicit multiplication by a name: 2π -> 2 * π, 3sin(x) -> 3 * sin(x). The
+        // name binds like the right operand of a multiplication, so 2π^2 -> 2 * (π^2)
+        if let Token::Ident(_) = self.curr_token {
+            let right = self.parse_expression(MULTIPLY)?;
 
-            return Some(Expression::Infix {
-                left: Box::new(left?),
+            return Ok(Expression::Infix {
+                span: left.span().to(right.span()),
+                left: Box::new(left),
                 operator: Token::Multiply,
-                right: Box::new(right?),
+                right: Box::new(right),
             });
         }
 
-        let op = self.curr_token?;
-        let precedense = op.precedence();
-        self.next_token();
-        let right = self.parse_expression(precedense);
+        let op = self.curr_token.clone();
+        let precedense = match op {
+            // Exponentiation is right-associative: 2^3^2 -> 2^(3^2)
+            Token::Caret => op.precedence() - 1,
+            _ => op.precedence(),
+        };
+        self.next_token()?;
+        let right = self.parse_expression(precedense)?;
 
-        Some(Expression::Infix {
-            left: Box::new(left?),
+        Ok(Expression::Infix {
+            span: left.span().to(right.span()),
+            left: Box::new(left),
             operator: op,
-            right: Box::new(right?),
+            right: Box::new(right),
         })
     }
 
-    fn parse_prefix(&mut self) -> Option<Expression> {
-        let op = self.curr_token?;
-        self.next_token();
-        let right = self.parse_expression(PREFIX);
+    fn parse_prefix(&mut self) -> Result<Expression, CalcError> {
+        let op = self.curr_token.clone();
+        let start = self.curr_span;
+        self.next_token()?;
+        let right = self.parse_expression(PREFIX)?;
 
-        Some(Expression::Prefix {
+        Ok(Expression::Prefix {
+            span: start.to(right.span()),
             operator: op,
-            right: Box::new(right?),
+            right: Box::new(right),
         })
     }
 
-    fn parse_unary(&mut self, left: Option<Expression>) -> Option<Expression> {
-        let op = self.curr_token?;
+    fn parse_unary(&mut self, left: Expression) -> Expression {
+        Expression::Unary {
+            span: left.span().to(self.curr_span),
+            operator: self.curr_token.clone(),
+            expression: Box::new(left),
+        }
+    }
 
-        Some(Expression::Unary {
-            operator: op,
-            expression: Box::new(left?),
+    /// Parses a function call such as `max(1, 2)`, leaving the closing parenthesis as the current
+    /// token.
+    fn parse_call(&mut self) -> Result<Expression, CalcError> {
+        let Token::Ident(name) = self.curr_token.clone() else {
+            return Err(CalcError::UnexpectedToken {
+                span: self.curr_span,
+            });
+        };
+        let start = self.curr_span;
+        self.next_token()?;
+        let open = self.curr_span;
+        let mut args = Vec::new();
+        self.open_parens += 1;
+
+        if self.peek_token == Token::RParen {
+            self.next_token()?;
+        } else {
+            loop {
+                self.next_token()?;
+                args.push(self.parse_expression(LOWEST)?);
+                self.next_token()?;
+
+                match self.curr_token {
+                    Token::Comma => continue,
+                    Token::RParen => break,
+                    Token::Eof => {
+                        self.expected = vec![
+                            TokenKind::Operator,
+                            TokenKind::Separator,
+                            TokenKind::CloseParen,
+                        ];
+                        return Err(CalcError::UnclosedParen { span: open });
+                    }
+                    _ => {
+                        return Err(CalcError::UnexpectedToken {
+                            span: self.curr_span,
+                        });
+                    }
+                }
+            }
+        }
+
+        self.open_parens -= 1;
+        Ok(Expression::Call {
+            name,
+            args,
+            span: start.to(self.curr_span),
         })
     }
 
-    fn parse_expression(&mut self, precedense: u8) -> Option<Expression> {
+    /// Parses a parenthesized expression, leaving the closing parenthesis as the current token.
+    fn parse_grouped(&mut self) -> Result<Expression, CalcError> {
+        let open = self.curr_span;
+        self.open_parens += 1;
+        self.next_token()?;
+        let expression = self.parse_expression(LOWEST)?;
+        self.next_token()?;
+
+        match self.curr_token {
+            Token::RParen => {
+                self.open_parens -= 1;
+                Ok(expression.widen(open.to(self.curr_span)))
+            }
+            Token::Eof => {
+                self.expected = vec![TokenKind::Operator, TokenKind::CloseParen];
+                Err(CalcError::UnclosedParen { span: open })
+            }
+            _ => Err(CalcError::UnexpectedToken {
+                span: self.curr_span,
+            }),
+        }
+    }
+
+    fn parse_expression(&mut self, precedense: u8) -> Result<Expression, CalcError> {
         let mut left = match &self.curr_token {
-            Some(Token::Eof) => return None,
-            Some(Token::Minus) => self.parse_prefix(),
-            Some(Token::LParen) => {
-                self.next_token();
-                let expr = self.parse_expression(LOWEST);
-                self.next_token();
-
-                if !self.test_current_token(Token::RParen) {
-                    return None;
+            Token::Eof => {
+                self.expected = TokenKind::OPERAND.to_vec();
+                return Err(CalcError::UnexpectedEnd {
+                    span: self.curr_span,
+                });
+            }
+            Token::Minus | Token::Not => self.parse_prefix()?,
+            Token::LParen => self.parse_grouped()?,
+            Token::Number(value) => Expression::Number {
+                value: value.clone(),
+                span: self.curr_span,
+            },
+            Token::Ident(name) => {
+                if self.peek_token == Token::LParen {
+                    self.parse_call()?
+                } else {
+                    Expression::Identifier {
+                        name: name.clone(),
+                        span: self.curr_span,
+                    }
                 }
-
-                expr
             }
-            Some(Token::Number(value)) => Some(Expression::Number { value: *value }),
-            _ => return None,
+            _ => {
+                return Err(CalcError::UnexpectedToken {
+                    span: self.curr_span,
+                });
+            }
         };
 
         while precedense < self.peek_precedence() {
-            self.next_token();
+            self.next_token()?;
 
-            match &self.curr_token {
-                Some(Token::Eof) => {
+            match self.curr_token {
+                Token::Eof => {
                     self.found_eof = true;
                     break;
                 }
-                Some(Token::Exclamation) => {
+                Token::Exclamation | Token::Percent => {
                     left = self.parse_unary(left);
                 }
-                _ => left = self.parse_infix(left),
+                _ => left = self.parse_infix(left)?,
             };
         }
 
-        left
-    }
-
-    fn peek_precedence(&mut self) -> u8 {
-        match &self.peek_token {
-            Some(token) => token.precedence(),
-            None => LOWEST,
-        }
+        Ok(left)
     }
 
-    fn test_current_token(&self, expected: Token) -> bool {
-        match &self.curr_token {
-            Some(t) => t == &expected,
-            None => false,
-        }
+    fn peek_precedence(&self) -> u8 {
+        self.peek_token.precedence()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::value::Value;
+
+    fn number(value: i64, start: usize, end: usize) -> Expression {
+        Expression::Number {
+            value: Value::from(value),
+            span: Span::new(start, end),
+        }
+    }
+
+    fn infix(left: Expression, operator: Token, right: Expression) -> Expression {
+        Expression::Infix {
+            span: left.span().to(right.span()),
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    fn grouped(expression: Expression, start: usize, end: usize) -> Expression {
+        expression.widen(Span::new(start, end))
+    }
 
     #[test]
     fn test_parser_empty() {
-        let input = vec![""];
+        let input = vec!["", "   "];
         let mut p = Parser::new();
         for expr in input {
             let result = p.parse(expr.to_string());
@@ -172,34 +434,28 @@ mod tests {
 
     #[test]
     fn test_simple_literals() {
-        let input = vec![
-            ("5", 5.0),
-            ("42", 42.0),
-            ("0", 0.0),
-            ("1234567890", 1234567890.0),
-        ];
+        let input = vec![("5", 5), ("42", 42), ("0", 0), ("1234567890", 1234567890)];
         let mut p = Parser::new();
         for expr in input {
             let result = p.parse(expr.0.to_string());
 
-            assert_eq!(result, Ok(Some(Expression::Number { value: expr.1 })));
+            assert_eq!(result, Ok(Some(number(expr.1, 0, expr.0.len()))));
         }
     }
 
     #[test]
     fn test_simple_negative_literals() {
-        let input = vec![("-5", 5.0), ("-42", 42.0), ("-1234567890", 1234567890.0)];
+        let input = vec![("-5", 5), ("-42", 42), ("-1234567890", 1234567890)];
         let mut p = Parser::new();
         for expr in input {
             let result = p.parse(expr.0.to_string());
 
-            // println!("Result for '{}': {:?}", expr.0, result);
-
             assert_eq!(
                 result,
                 Ok(Some(Expression::Prefix {
                     operator: Token::Minus,
-                    right: Box::new(Expression::Number { value: expr.1 })
+                    right: Box::new(number(expr.1, 1, expr.0.len())),
+                    span: Span::new(0, expr.0.len()),
                 }))
             );
         }
@@ -207,49 +463,113 @@ mod tests {
 
     #[test]
     fn test_parser_incomplete() {
-        let input = vec!["-", "(399", "*", "3-", "-5+"];
+        let input = vec![
+            (
+                "-",
+                CalcError::UnexpectedEnd {
+                    span: Span::new(1, 1),
+                },
+            ),
+            (
+                "(399",
+                CalcError::UnclosedParen {
+                    span: Span::new(0, 1),
+                },
+            ),
+            (
+                "*",
+                CalcError::UnexpectedToken {
+                    span: Span::new(0, 1),
+                },
+            ),
+            (
+                "3-",
+                CalcError::UnexpectedEnd {
+                    span: Span::new(2, 2),
+                },
+            ),
+            (
+                "-5+",
+                CalcError::UnexpectedEnd {
+                    span: Span::new(3, 3),
+                },
+            ),
+        ];
         let mut p = Parser::new();
-        for expr in input {
+        for (expr, expected) in input {
             let result = p.parse(expr.to_string());
-            assert_eq!(result, Ok(None));
+            assert_eq!(result, Err(expected));
+        }
+    }
+
+    #[test]
+    fn test_parser_invalid() {
+        let input = vec![
+            (
+                "(3))",
+                CalcError::UnbalancedParen {
+                    span: Span::new(3, 4),
+                },
+            ),
+            (
+                "5 3",
+                CalcError::UnexpectedToken {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "(5 3)",
+                CalcError::UnexpectedToken {
+                    span: Span::new(3, 4),
+                },
+            ),
+            (
+                "2+)",
+                CalcError::UnexpectedToken {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "2+@",
+                CalcError::UnknownCharacter {
+                    ch: '@',
+                    span: Span::new(2, 3),
+                },
+            ),
+        ];
+        let mut p = Parser::new();
+        for (expr, expected) in input {
+            let result = p.parse(expr.to_string());
+            assert_eq!(result, Err(expected));
         }
     }
 
     #[test]
     fn test_parser_complete_simple_expression() {
-        let input: Vec<(&str, Result<Option<Expression>, String>)> = vec![
+        let input: Vec<(&str, Result<Option<Expression>, CalcError>)> = vec![
             (
                 "15+3",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 15.0 }),
-
-                    operator: Token::Plus,
-                    right: Box::new(Expression::Number { value: 3.0 }),
-                })),
+                Ok(Some(infix(number(15, 0, 2), Token::Plus, number(3, 3, 4)))),
             ),
             (
                 "15-3",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 15.0 }),
-                    operator: Token::Minus,
-                    right: Box::new(Expression::Number { value: 3.0 }),
-                })),
+                Ok(Some(infix(number(15, 0, 2), Token::Minus, number(3, 3, 4)))),
             ),
             (
                 "15*3",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 15.0 }),
-                    operator: Token::Multiply,
-                    right: Box::new(Expression::Number { value: 3.0 }),
-                })),
+                Ok(Some(infix(
+                    number(15, 0, 2),
+                    Token::Multiply,
+                    number(3, 3, 4),
+                ))),
             ),
             (
                 "15/3",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 15.0 }),
-                    operator: Token::Divide,
-                    right: Box::new(Expression::Number { value: 3.0 }),
-                })),
+                Ok(Some(infix(
+                    number(15, 0, 2),
+                    Token::Divide,
+                    number(3, 3, 4),
+                ))),
             ),
         ];
 
@@ -262,71 +582,58 @@ mod tests {
 
     #[test]
     fn test_parser_complete_complex_expressions() {
-        let input: Vec<(&str, Result<Option<Expression>, String>)> = vec![
+        let input: Vec<(&str, Result<Option<Expression>, CalcError>)> = vec![
             (
                 "5*(3-1)",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 5.0 }),
-                    operator: Token::Multiply,
-                    right: Box::new(Expression::Infix {
-                        left: Box::new(Expression::Number { value: 3.0 }),
-
-                        operator: Token::Minus,
-                        right: Box::new(Expression::Number { value: 1.0 }),
-                    }),
-                })),
+                Ok(Some(infix(
+                    number(5, 0, 1),
+                    Token::Multiply,
+                    grouped(infix(number(3, 3, 4), Token::Minus, number(1, 5, 6)), 2, 7),
+                ))),
             ),
             (
                 "5(3-1)",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 5.0 }),
-                    operator: Token::Multiply,
-                    right: Box::new(Expression::Infix {
-                        left: Box::new(Expression::Number { value: 3.0 }),
-                        operator: Token::Minus,
-                        right: Box::new(Expression::Number { value: 1.0 }),
-                    }),
-                })),
+                Ok(Some(infix(
+                    number(5, 0, 1),
+                    Token::Multiply,
+                    grouped(infix(number(3, 2, 3), Token::Minus, number(1, 4, 5)), 1, 6),
+                ))),
             ),
             (
                 "5*(3-1*4+8)/2",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Infix {
-                        left: Box::new(Expression::Number { value: 5.0 }),
-                        operator: Token::Multiply,
-                        right: Box::new(Expression::Infix {
-                            left: Box::new(Expression::Infix {
-                                left: Box::new(Expression::Number { value: 3.0 }),
-                                operator: Token::Minus,
-                                right: Box::new(Expression::Infix {
-                                    left: Box::new(Expression::Number { value: 1.0 }),
-                                    operator: Token::Multiply,
-                                    right: Box::new(Expression::Number { value: 4.0 }),
-                                }),
-                            }),
-                            operator: Token::Plus,
-                            right: Box::new(Expression::Number { value: 8.0 }),
-                        }),
-                    }),
-                    operator: Token::Divide,
-                    right: Box::new(Expression::Number { value: 2.0 }),
-                })),
+                Ok(Some(infix(
+                    infix(
+                        number(5, 0, 1),
+                        Token::Multiply,
+                        grouped(
+                            infix(
+                                infix(
+                                    number(3, 3, 4),
+                                    Token::Minus,
+                                    infix(number(1, 5, 6), Token::Multiply, number(4, 7, 8)),
+                                ),
+                                Token::Plus,
+                                number(8, 9, 10),
+                            ),
+                            2,
+                            11,
+                        ),
+                    ),
+                    Token::Divide,
+                    number(2, 12, 13),
+                ))),
             ),
             (
                 "42-7*(2+3)",
-                Ok(Some(Expression::Infix {
-                    left: Box::new(Expression::Number { value: 42.0 }),
-                    operator: Token::Minus,
-                    right: Box::new(Expression::Infix {
-                        left: Box::new(Expression::Number { value: 7.0 }),
-                        operator: Token::Multiply,
-                        right: Box::new(Expression::Infix {
-                            left: Box::new(Expression::Number { value: 2.0 }),
-                            operator: Token::Plus,
-                            right: Box::new(Expression::Number { value: 3.0 }),
-                        }),
-                    }),
-                })),
+                Ok(Some(infix(
+                    number(42, 0, 2),
+                    Token::Minus,
+                    infix(
+                        number(7, 3, 4),
+                        Token::Multiply,
+                        grouped(infix(number(2, 6, 7), Token::Plus, number(3, 8, 9)), 5, 10),
+                    ),
+                ))),
             ),
         ];
 
@@ -336,4 +643,430 @@ mod tests {
             assert_eq!(result, expected_tokens);
         }
     }
+
+    #[test]
+    fn test_parser_power_is_right_associative() {
+        let mut p = Parser::new();
+        let result = p.parse("2^3^2".to_string());
+
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                number(2, 0, 1),
+                Token::Caret,
+                infix(number(3, 2, 3), Token::Caret, number(2, 4, 5)),
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_prefix_minus_binds_looser_than_power() {
+        let mut p = Parser::new();
+        let result = p.parse("-2^2".to_string());
+
+        assert_eq!(
+            result,
+            Ok(Some(Expression::Prefix {
+                operator: Token::Minus,
+                right: Box::new(infix(number(2, 1, 2), Token::Caret, number(2, 3, 4))),
+                span: Span::new(0, 4),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parser_bitwise_precedence() {
+        let mut p = Parser::new();
+
+        // Shifts bind looser than addition, and `&` tighter than `xor` tighter than `|`
+        let result = p.parse("1|2 xor 3&4<<5+6".to_string());
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                number(1, 0, 1),
+                Token::BitOr,
+                infix(
+                    number(2, 2, 3),
+                    Token::Xor,
+                    infix(
+                        number(3, 8, 9),
+                        Token::BitAnd,
+                        infix(
+                            number(4, 10, 11),
+                            Token::ShiftLeft,
+                            infix(number(5, 13, 14), Token::Plus, number(6, 15, 16)),
+                        ),
+                    ),
+                ),
+            )))
+        );
+
+        let result = p.parse("~1&2".to_string());
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                Expression::Prefix {
+                    operator: Token::Not,
+                    right: Box::new(number(1, 1, 2)),
+                    span: Span::new(0, 2),
+                },
+                Token::BitAnd,
+                number(2, 3, 4),
+            )))
+        );
+
+        let result = p.parse("not(1) rol 2".to_string());
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                Expression::Prefix {
+                    operator: Token::Not,
+                    right: Box::new(grouped(number(1, 4, 5), 3, 6)),
+                    span: Span::new(0, 6),
+                },
+                Token::RotateLeft,
+                number(2, 11, 12),
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_postfix_percent() {
+        let mut p = Parser::new();
+        let result = p.parse("200+10%".to_string());
+
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                number(200, 0, 3),
+                Token::Plus,
+                Expression::Unary {
+                    operator: Token::Percent,
+                    expression: Box::new(number(10, 4, 6)),
+                    span: Span::new(4, 7),
+                },
+            )))
+        );
+
+        let result = p.parse("200/10%".to_string());
+
+        assert_eq!(
+            result,
+            Ok(Some(infix(
+                number(200, 0, 3),
+                Token::Divide,
+                Expression::Unary {
+                    operator: Token::Percent,
+                    expression: Box::new(number(10, 4, 6)),
+                    span: Span::new(4, 7),
+                },
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parser_function_calls() {
+        let input: Vec<(&str, Result<Option<Expression>, CalcError>)> = vec![
+            (
+                "sqrt(4)",
+                Ok(Some(Expression::Call {
+                    name: "sqrt".to_string(),
+                    args: vec![number(4, 5, 6)],
+                    span: Span::new(0, 7),
+                })),
+            ),
+            (
+                "max(1,2+3)",
+                Ok(Some(Expression::Call {
+                    name: "max".to_string(),
+                    args: vec![
+                        number(1, 4, 5),
+                        infix(number(2, 6, 7), Token::Plus, number(3, 8, 9)),
+                    ],
+                    span: Span::new(0, 10),
+                })),
+            ),
+            (
+                "pi()",
+                Ok(Some(Expression::Call {
+                    name: "pi".to_string(),
+                    args: vec![],
+                    span: Span::new(0, 4),
+                })),
+            ),
+            (
+                "2*sin(0)^2",
+                Ok(Some(infix(
+                    number(2, 0, 1),
+                    Token::Multiply,
+                    infix(
+                        Expression::Call {
+                            name: "sin".to_string(),
+                            args: vec![number(0, 6, 7)],
+                            span: Span::new(2, 8),
+                        },
+                        Token::Caret,
+                        number(2, 9, 10),
+                    ),
+                ))),
+            ),
+            (
+                "max(1,",
+                Err(CalcError::UnexpectedEnd {
+                    span: Span::new(6, 6),
+                }),
+            ),
+            (
+                "max(1,2",
+                Err(CalcError::UnclosedParen {
+                    span: Span::new(3, 4),
+                }),
+            ),
+        ];
+
+        let mut p = Parser::new();
+        for (expr, expected) in input {
+            let result = p.parse(expr.to_string());
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_parser_identifiers_and_implicit_multiplication() {
+        let identifier = |name: &str, start: usize, end: usize| Expression::Identifier {
+            name: name.to_string(),
+            span: Span::new(start, end),
+        };
+
+        let input: Vec<(&str, Result<Option<Expression>, CalcError>)> = vec![
+            ("pi", Ok(Some(identifier("pi", 0, 2)))),
+            (
+                "2π",
+                Ok(Some(infix(
+                    number(2, 0, 1),
+                    Token::Multiply,
+                    identifier("pi", 1, 3),
+                ))),
+            ),
+            (
+                "3e",
+                Ok(Some(infix(
+                    number(3, 0, 1),
+                    Token::Multiply,
+                    identifier("e", 1, 2),
+                ))),
+            ),
+            (
+                "2π^2",
+                Ok(Some(infix(
+                    number(2, 0, 1),
+                    Token::Multiply,
+                    infix(identifier("pi", 1, 3), Token::Caret, number(2, 4, 5)),
+                ))),
+            ),
+            (
+                "1/2π",
+                Ok(Some(infix(
+                    number(1, 0, 1),
+                    Token::Divide,
+                    infix(number(2, 2, 3), Token::Multiply, identifier("pi", 3, 5)),
+                ))),
+            ),
+            (
+                "2sqrt(4)",
+                Ok(Some(infix(
+                    number(2, 0, 1),
+                    Token::Multiply,
+                    Expression::Call {
+                        name: "sqrt".to_string(),
+                        args: vec![number(4, 6, 7)],
+                        span: Span::new(1, 8),
+                    },
+                ))),
+            ),
+            (
+                "2 3",
+                Err(CalcError::UnexpectedToken {
+                    span: Span::new(2, 3),
+                }),
+            ),
+        ];
+
+        let mut p = Parser::new();
+        for (expr, expected) in input {
+            let result = p.parse(expr.to_string());
+            assert_eq!(result, expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_parser_assignment() {
+        let input: Vec<(&str, Result<Option<Expression>, CalcError>)> = vec![
+            (
+                "x = 5",
+                Ok(Some(Expression::Assign {
+                    name: "x".to_string(),
+                    value: Box::new(number(5, 4, 5)),
+                    span: Span::new(0, 5),
+                })),
+            ),
+            (
+                "rate=1+2",
+                Ok(Some(Expression::Assign {
+                    name: "rate".to_string(),
+                    value: Box::new(infix(number(1, 5, 6), Token::Plus, number(2, 7, 8))),
+                    span: Span::new(0, 8),
+                })),
+            ),
+            (
+                "x=",
+                Err(CalcError::UnexpectedEnd {
+                    span: Span::new(2, 2),
+                }),
+            ),
+            (
+                "x=1=2",
+                Err(CalcError::UnexpectedToken {
+                    span: Span::new(3, 4),
+                }),
+            ),
+            (
+                "2x=1",
+                Err(CalcError::InvalidAssignment {
+                    span: Span::new(0, 2),
+                }),
+            ),
+            (
+                "f(x) = x^2",
+                Ok(Some(Expression::Define {
+                    name: "f".to_string(),
+                    params: vec!["x".to_string()],
+                    body: Box::new(infix(
+                        Expression::Identifier {
+                            name: "x".to_string(),
+                            span: Span::new(7, 8),
+                        },
+                        Token::Caret,
+                        number(2, 9, 10),
+                    )),
+                    span: Span::new(0, 10),
+                })),
+            ),
+            (
+                "g(a,b)=1",
+                Ok(Some(Expression::Define {
+                    name: "g".to_string(),
+                    params: vec!["a".to_string(), "b".to_string()],
+                    body: Box::new(number(1, 7, 8)),
+                    span: Span::new(0, 8),
+                })),
+            ),
+            (
+                "k()=1",
+                Ok(Some(Expression::Define {
+                    name: "k".to_string(),
+                    params: vec![],
+                    body: Box::new(number(1, 4, 5)),
+                    span: Span::new(0, 5),
+                })),
+            ),
+            (
+                "f(x+1)=2",
+                Err(CalcError::InvalidAssignment {
+                    span: Span::new(2, 5),
+                }),
+            ),
+        ];
+
+        let mut p = Parser::new();
+        for (expr, expected) in input {
+            let result = p.parse(expr.to_string());
+            assert_eq!(result, expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let operand = TokenKind::OPERAND.to_vec();
+        let group = vec![TokenKind::Operator, TokenKind::CloseParen];
+        let call = vec![
+            TokenKind::Operator,
+            TokenKind::Separator,
+            TokenKind::CloseParen,
+        ];
+
+        let inputs = vec![
+            ("", Some((operand.clone(), 0))),
+            ("3-", Some((operand.clone(), 0))),
+            ("-", Some((operand.clone(), 0))),
+            ("(1+", Some((operand.clone(), 1))),
+            ("x =", Some((operand.clone(), 0))),
+            ("(399", Some((group.clone(), 1))),
+            ("2*(3-(4", Some((group.clone(), 2))),
+            ("(1)*(2", Some((group.clone(), 1))),
+            ("max(1, 2", Some((call.clone(), 1))),
+            ("max(1, (2", Some((group, 2))),
+            ("(max(1", Some((call.clone(), 2))),
+            ("(pi()+max(1", Some((call, 2))),
+            ("3-1", None),
+            ("(399)", None),
+            ("f(x) = x^2", None),
+        ];
+        let context = EvaluationContext::default();
+
+        for i in inputs {
+            match (parse(i.0, &context), i.1) {
+                (
+                    ParseStatus::Incomplete {
+                        expected,
+                        open_parens,
+                    },
+                    Some(incomplete),
+                ) => {
+                    assert_eq!((expected, open_parens), incomplete, "{}", i.0)
+                }
+                (ParseStatus::Complete(expr), None) => {
+                    assert_eq!(expr.span(), Span::new(0, i.0.len()), "{}", i.0)
+                }
+                (status, _) => panic!("{}: {:?}", i.0, status),
+            }
+        }
+
+        let invalid = vec![
+            (
+                "*",
+                CalcError::UnexpectedToken {
+                    span: Span::new(0, 1),
+                },
+            ),
+            (
+                "(3))",
+                CalcError::UnbalancedParen {
+                    span: Span::new(3, 4),
+                },
+            ),
+            (
+                "2+@",
+                CalcError::UnknownCharacter {
+                    ch: '@',
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "2x=1",
+                CalcError::InvalidAssignment {
+                    span: Span::new(0, 2),
+                },
+            ),
+        ];
+
+        for (input, err) in invalid {
+            assert_eq!(
+                parse(input, &context),
+                ParseStatus::Invalid(err),
+                "{}",
+                input
+            );
+        }
+    }
 }
@@ -1,34 +1,60 @@
-#[derive(Copy, PartialEq, Clone, Debug)]
+use crate::value::Value;
+
+#[derive(PartialEq, Clone, Debug)]
 pub(crate) enum Token {
-    Number(f64),
+    Number(Value),
+    Ident(String),
     Plus,
     Minus,
     Multiply,
     Divide,
     LParen,
     RParen,
+    Comma,
     Percent,
     Caret,
     Exclamation,
-    Eof, // equal sign or newline
+    Mod,
+    BitAnd,
+    BitOr,
+    Xor,
+    /// Bitwise complement, `~` or `not`.
+    Not,
+    ShiftLeft,
+    ShiftRight,
+    RotateLeft,
+    RotateRight,
+    Assign,
+    Eof, // end of input
 }
 
 pub(crate) const LOWEST: u8 = 0;
 pub(crate) const EOF: u8 = 1;
+pub(crate) const BIT_OR: u8 = 4;
+pub(crate) const BIT_XOR: u8 = 5;
+pub(crate) const BIT_AND: u8 = 6;
+pub(crate) const SHIFT: u8 = 8;
 pub(crate) const ADD: u8 = 10;
 pub(crate) const MULTIPLY: u8 = 20;
 pub(crate) const PREFIX: u8 = 30;
 pub(crate) const EXPONENT: u8 = 40;
+pub(crate) const POSTFIX: u8 = 45;
 pub(crate) const PARENTHETICAL: u8 = 50;
 
 impl Token {
     pub(crate) fn precedence(&self) -> u8 {
         match self {
             Token::Eof => EOF,
+            Token::BitOr => BIT_OR,
+            Token::Xor => BIT_XOR,
+            Token::BitAnd => BIT_AND,
+            Token::ShiftLeft | Token::ShiftRight | Token::RotateLeft | Token::RotateRight => SHIFT,
             Token::Plus | Token::Minus => ADD,
-            Token::Multiply | Token::Divide | Token::Percent | Token::Exclamation => MULTIPLY,
+            Token::Multiply | Token::Divide | Token::Mod => MULTIPLY,
             Token::Caret => EXPONENT,
-            Token::LParen => PARENTHETICAL,
+            Token::Exclamation | Token::Percent => POSTFIX,
+            // A name or parenthesis directly after an operand is an implicit multiplication
+            Token::LParen | Token::Ident(_) => PARENTHETICAL,
             _ => LOWEST,
         }
     }
@@ -1,4 +1,4 @@
-///
+/// Returns num with its sign forced to negative or positive.
 pub(crate) fn change_sign(num: f64, make_negative: bool) -> f64 {
     if make_negative { -num.abs() } else { num.abs() }
 }
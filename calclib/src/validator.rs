@@ -1,8 +1,15 @@
-/// Validates if the input character is one of the allowed mathematical symbols or digits.
+use crate::locale::Locale;
+
+/// Validates if the input character is one of the allowed mathematical symbols, digits or letters.
 pub fn validate(input: &char) -> bool {
     matches!(
         input,
         '0'..='9'
+            | 'a'..='z'
+            | 'A'..='Z'
+            | '_'
+            | ','
+            | ' '
             | '+'
             | '-'
             | '*'
@@ -14,12 +21,29 @@ pub fn validate(input: &char) -> bool {
             | '.'
             | '='
             | '!'
+            | '&'
+            | '|'
+            | '~'
+            | '<'
+            | '>'
             | '×'
             | '÷'
             | '−'
+            | 'π'
+            | 'τ'
+            | 'φ'
     )
 }
 
+/// Validates the input character as `validate` does, also allowing the separators of locale such as
+/// the `;` between arguments where the decimal separator is a comma.
+pub fn validate_localized(input: &char, locale: &Locale) -> bool {
+    validate(input)
+        || *input == locale.decimal_separator
+        || locale.is_group_separator(*input)
+        || *input == locale.argument_separator()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,7 +51,22 @@ mod tests {
     fn test_validate_with_valid_chars() {
         let valid_chars = vec![
             '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '+', '-', '*', '/', '(', ')', '.',
-            '^', '%', '!', '=', '×', '÷', '−',
+            '^', '%', '!', '=', '×', '÷', '−', 'π', 'τ', 'φ', '&', '|', '~', '<', '>',
+        ];
+
+        for ch in valid_chars {
+            assert!(validate(&ch), "Character '{}' should be valid", ch);
+        }
+    }
+
+    #[test]
+    fn test_validate_with_identifier_chars() {
+        // Letters, underscores, commas and spaces make up function calls such as max(a_1, b)
+        let valid_chars = vec![
+            'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q',
+            'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H',
+            'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y',
+            'Z', '_', ',', ' ',
         ];
 
         for ch in valid_chars {
@@ -39,15 +78,27 @@ mod tests {
     fn test_validate_with_invalid_chars() {
         // Invalid insert action
         let invalid_chars = vec![
-            'a', 'b', 'c', ' ', '@', '#', '$', '&', '_', '[', ']', '{', '}', ';', ':', '"', '\'',
-            '<', '>', ',', '?', '\\', '|', '~', '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i',
-            'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-            'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
-            'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', ' ',
+            '@', '#', '$', '[', ']', '{', '}', ';', ':', '"', '\'', '?', '\\', '`', 'é', 'ß',
         ];
 
         for ch in invalid_chars {
             assert!(!validate(&ch), "Character '{}' should be invalid", ch);
         }
     }
+
+    #[test]
+    fn test_validate_localized() {
+        let german = Locale::from_language("de");
+        let swiss = Locale::from_language("de-CH");
+
+        assert!(validate_localized(&';', &german));
+        assert!(validate_localized(&'\'', &swiss));
+        assert!(validate_localized(&'5', &german));
+        assert!(!validate_localized(&';', &Locale::default()));
+        assert!(!validate_localized(&'\'', &german));
+        assert!(validate_localized(
+            &'\u{202F}',
+            &Locale::from_language("fr")
+        ));
+    }
 }
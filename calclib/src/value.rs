@@ -0,0 +1,696 @@
+use crate::context::{Arithmetic, Rounding};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, Pow, Signed, ToPrimitive, Zero};
+use rust_decimal::{Decimal, MathematicalOps, RoundingStrategy};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// The largest integer, in bits, computed exactly. Larger results fall back to floating point,
+/// where they overflow.
+pub(crate) const MAX_INTEGER_BITS: u64 = 1 << 20;
+
+/// The largest integer whose factorial is computed exactly.
+const MAX_EXACT_FACTORIAL: u32 = 10_000;
+
+/// The most decimal places shown for a fraction whose decimal expansion terminates. Longer
+/// expansions are shown as floating point.
+const MAX_EXACT_DECIMALS: usize = 100;
+
+/// A number produced while evaluating an expression. Integers and fractions are exact and
+/// arbitrarily large; an operation without an exact result falls back to floating point.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Integer(BigInt),
+    /// A fraction that is not an integer.
+    Rational(BigRational),
+    /// A base-10 decimal, used by decimal arithmetic.
+    Decimal(Decimal),
+    Float(f64),
+}
+
+impl Value {
+    /// Wraps an exact fraction, as an integer when its denominator is one.
+    pub(crate) fn exact(r: BigRational) -> Value {
+        if r.is_integer() {
+            Value::Integer(r.to_integer())
+        } else {
+            Value::Rational(r)
+        }
+    }
+
+    /// Parses a decimal literal such as `12.5` or `.5` exactly.
+    pub(crate) fn parse_decimal(s: &str) -> Option<Value> {
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let digits: BigInt = format!("0{}{}", whole, fraction).parse().ok()?;
+        let scale = BigInt::from(10).pow(fraction.len());
+
+        Some(Value::exact(BigRational::new(digits, scale)))
+    }
+
+    /// Parses the digits of an integer literal in radix, such as the `FF` of `0xFF`.
+    pub(crate) fn parse_radix(digits: &str, radix: u32) -> Option<Value> {
+        if !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        BigInt::parse_bytes(digits.as_bytes(), radix).map(Value::Integer)
+    }
+
+    /// The value as an exact fraction, unless it is a decimal or floating point.
+    fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Value::Integer(n) => Some(BigRational::from_integer(n.clone())),
+            Value::Rational(r) => Some(r.clone()),
+            Value::Decimal(_) | Value::Float(_) => None,
+        }
+    }
+
+    /// The value as an exact fraction, including decimals, unless it is floating point.
+    pub(crate) fn to_exact(&self) -> Option<BigRational> {
+        match self {
+            Value::Decimal(d) => Some(BigRational::new(
+                BigInt::from(d.mantissa()),
+                BigInt::from(10).pow(d.scale()),
+            )),
+            _ => self.to_rational(),
+        }
+    }
+
+    /// The value as a decimal, or `None` if it is out of range.
+    fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Integer(n) => Decimal::try_from_i128_with_scale(n.to_i128()?, 0).ok(),
+            Value::Rational(r) => Value::Integer(r.numer().clone())
+                .to_decimal()?
+                .checked_div(Value::Integer(r.denom().clone()).to_decimal()?),
+            Value::Decimal(d) => Some(*d),
+            Value::Float(f) => Decimal::from_f64(*f),
+        }
+    }
+
+    /// The value as an integer, if it is an exact integer or a decimal without a fractional part.
+    pub(crate) fn to_integer(&self) -> Option<BigInt> {
+        match self {
+            Value::Integer(n) => Some(n.clone()),
+            Value::Decimal(d) if d.is_integer() => d.to_i128().map(BigInt::from),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_f64(&self) -> f64 {
+        match self {
+            Value::Integer(n) => n.to_f64().unwrap_or(f64::NAN),
+            Value::Rational(r) => r.to_f64().unwrap_or(f64::NAN),
+            Value::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+            Value::Float(f) => *f,
+        }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        match self {
+            Value::Integer(n) => n.is_zero(),
+            Value::Rational(r) => r.is_zero(),
+            Value::Decimal(d) => d.is_zero(),
+            Value::Float(f) => *f == 0.0,
+        }
+    }
+
+    pub(crate) fn is_negative(&self) -> bool {
+        match self {
+            Value::Integer(n) => n.is_negative(),
+            Value::Rational(r) => r.is_negative(),
+            Value::Decimal(d) => d.is_sign_negative() && !d.is_zero(),
+            Value::Float(f) => *f < 0.0,
+        }
+    }
+
+    /// Converts the value to the representation used by arithmetic. Values that do not fit a
+    /// decimal are left unchanged.
+    pub(crate) fn with_arithmetic(self, arithmetic: Arithmetic) -> Value {
+        match (arithmetic, &self) {
+            (Arithmetic::Decimal { .. }, Value::Decimal(_))
+            | (Arithmetic::Integer { .. }, Value::Integer(_))
+            | (Arithmetic::Exact, _) => self,
+            (Arithmetic::Decimal { .. }, _) => self.to_decimal().map_or(self, Value::Decimal),
+            (Arithmetic::Integer { .. }, _) => self.truncate().map_or(self, Value::Integer),
+        }
+    }
+
+    /// The integer part of the value, or `None` if it is infinite or NaN.
+    fn truncate(&self) -> Option<BigInt> {
+        match self {
+            Value::Integer(n) => Some(n.clone()),
+            Value::Rational(r) => Some(r.trunc().to_integer()),
+            Value::Decimal(d) => d.trunc().to_i128().map(BigInt::from),
+            Value::Float(f) => BigInt::from_f64(f.trunc()),
+        }
+    }
+
+    /// Rounds a decimal to the precision of decimal arithmetic, keeping trailing zeros so that
+    /// 3.3 is shown as 3.30.
+    pub(crate) fn round(self, arithmetic: Arithmetic) -> Value {
+        match (arithmetic, self) {
+            (
+                Arithmetic::Decimal {
+                    precision,
+                    rounding,
+                },
+                Value::Decimal(d),
+            ) => {
+                let precision = precision.min(Arithmetic::MAX_PRECISION);
+                let strategy = match rounding {
+                    Rounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+                    Rounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+                    Rounding::Truncate => RoundingStrategy::ToZero,
+                };
+
+                let mut rounded = d.round_dp_with_strategy(precision, strategy);
+                rounded.rescale(precision);
+                Value::Decimal(rounded)
+            }
+            (_, value) => value,
+        }
+    }
+
+    /// The value as a fraction such as `-7/3`, if it is exact but not an integer.
+    pub(crate) fn fraction(&self) -> Option<String> {
+        match self {
+            Value::Rational(r) => Some(format!("{}/{}", r.numer(), r.denom())),
+            _ => None,
+        }
+    }
+
+    /// Applies exact to a pair of exact values, decimal to a pair involving a decimal, or float to
+    /// both values as floating point. A decimal operation that overflows falls back to floating
+    /// point.
+    fn combine(
+        self,
+        other: Value,
+        exact: impl FnOnce(BigRational, BigRational) -> Value,
+        decimal: impl FnOnce(Decimal, Decimal) -> Option<Decimal>,
+        float: impl FnOnce(f64, f64) -> f64,
+    ) -> Value {
+        if matches!(self, Value::Decimal(_)) || matches!(other, Value::Decimal(_)) {
+            if let (Some(a), Some(b)) = (self.to_decimal(), other.to_decimal())
+                && let Some(result) = decimal(a, b)
+            {
+                return Value::Decimal(result);
+            }
+        } else if let (Some(a), Some(b)) = (self.to_rational(), other.to_rational()) {
+            return exact(a, b);
+        }
+
+        Value::Float(float(self.to_f64(), other.to_f64()))
+    }
+
+    /// Computes the floored remainder of self / divisor, so the result takes the sign of the
+    /// divisor (-7 mod 3 = 2). The divisor must not be zero.
+    pub(crate) fn modulo(self, divisor: Value) -> Value {
+        self.combine(
+            divisor,
+            |a, b| Value::exact(&a - &b * (&a / &b).floor()),
+            |a, b| a.checked_sub(b.checked_mul(a.checked_div(b)?.floor())?),
+            |a, b| a - b * (a / b).floor(),
+        )
+    }
+
+    /// Raises self to the power of exponent, exactly when self is exact and the exponent is an
+    /// integer.
+    pub(crate) fn pow(self, exponent: Value) -> Value {
+        if let (Some(base), Value::Integer(exp)) = (self.to_rational(), &exponent)
+            && let Some(exp) = exp.to_i32()
+            && bits(&base) * u64::from(exp.unsigned_abs()) <= MAX_INTEGER_BITS
+            && !(base.is_zero() && exp < 0)
+        {
+            return Value::exact(base.pow(exp));
+        }
+
+        if let Value::Decimal(base) = &self
+            && let Some(exp) = exponent.to_integer().and_then(|exp| exp.to_i64())
+            && let Some(result) = base.checked_powi(exp)
+        {
+            return Value::Decimal(result);
+        }
+
+        let (base, exponent) = (self.to_f64(), exponent.to_f64());
+        if exponent.fract() == 0.0 && exponent.abs() <= i32::MAX as f64 {
+            Value::Float(base.powi(exponent as i32))
+        } else {
+            Value::Float(base.powf(exponent))
+        }
+    }
+}
+
+/// The number of bits needed to hold the numerator and denominator of r.
+fn bits(r: &BigRational) -> u64 {
+    r.numer().bits() + r.denom().bits()
+}
+
+/// The factorial of n, or `None` if it is too large to compute exactly. A negative n gives the
+/// negated factorial of its magnitude, so (-5)! = -120.
+pub(crate) fn integer_factorial(n: &BigInt) -> Option<BigInt> {
+    let magnitude = n
+        .magnitude()
+        .to_u32()
+        .filter(|m| *m <= MAX_EXACT_FACTORIAL)?;
+    let result = (2..=magnitude).fold(BigInt::from(1), |acc, k| acc * k);
+
+    Some(if n.is_negative() { -result } else { result })
+}
+
+/// The exact decimal expansion of r, if it terminates within `MAX_EXACT_DECIMALS` places.
+fn terminating_decimal(r: &BigRational) -> Option<String> {
+    // The expansion terminates when the denominator has no prime factors other than 2 and 5
+    let mut denom = r.denom().clone();
+    let mut places = [0usize; 2];
+    for (i, factor) in [2, 5].into_iter().enumerate() {
+        let factor = BigInt::from(factor);
+        while (&denom % &factor).is_zero() {
+            denom /= &factor;
+            places[i] += 1;
+        }
+    }
+    let places = places[0].max(places[1]);
+    if denom != BigInt::from(1) || places > MAX_EXACT_DECIMALS {
+        return None;
+    }
+
+    let scaled = (r * BigRational::from_integer(BigInt::from(10).pow(places))).to_integer();
+    let digits = format!("{:0>width$}", scaled.magnitude(), width = places + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - places);
+    let sign = if r.is_negative() { "-" } else { "" };
+
+    Some(format!("{}{}.{}", sign, whole, fraction))
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Integer(BigInt::from(n))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl Add for Value {
+    type Output = Value;
+
+    fn add(self, other: Value) -> Value {
+        self.combine(
+            other,
+            |a, b| Value::exact(a + b),
+            |a, b| a.checked_add(b),
+            |a, b| a + b,
+        )
+    }
+}
+
+impl Sub for Value {
+    type Output = Value;
+
+    fn sub(self, other: Value) -> Value {
+        self.combine(
+            other,
+            |a, b| Value::exact(a - b),
+            |a, b| a.checked_sub(b),
+            |a, b| a - b,
+        )
+    }
+}
+
+impl Mul for Value {
+    type Output = Value;
+
+    fn mul(self, other: Value) -> Value {
+        self.combine(
+            other,
+            |a, b| {
+                if bits(&a) + bits(&b) <= MAX_INTEGER_BITS {
+                    Value::exact(a * b)
+                } else {
+                    Value::Float(a.to_f64().unwrap_or(f64::NAN) * b.to_f64().unwrap_or(f64::NAN))
+                }
+            },
+            |a, b| a.checked_mul(b),
+            |a, b| a * b,
+        )
+    }
+}
+
+/// The divisor must not be zero.
+impl Div for Value {
+    type Output = Value;
+
+    fn div(self, other: Value) -> Value {
+        self.combine(
+            other,
+            |a, b| Value::exact(a / b),
+            |a, b| a.checked_div(b),
+            |a, b| a / b,
+        )
+    }
+}
+
+impl Neg for Value {
+    type Output = Value;
+
+    fn neg(self) -> Value {
+        match self {
+            Value::Integer(n) => Value::Integer(-n),
+            Value::Rational(r) => Value::Rational(-r),
+            Value::Decimal(d) => Value::Decimal(-d),
+            Value::Float(f) => Value::Float(-f),
+        }
+    }
+}
+
+/// Shows the value in decimal; see `Value::fraction` for exact fractions.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Rational(r) => match terminating_decimal(r) {
+                Some(decimal) => write!(f, "{}", decimal),
+                None => write!(f, "{}", self.to_f64()),
+            },
+            Value::Decimal(d) => write!(f, "{}", d),
+            Value::Float(x) if x.fract() == 0.0 && x.abs() <= i64::MAX as f64 => {
+                write!(f, "{}", x.trunc() as i64)
+            }
+            Value::Float(x) if x.fract() == 0.0 => write!(f, "{:e}", x),
+            Value::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integer(s: &str) -> Value {
+        Value::Integer(s.parse().unwrap())
+    }
+
+    fn rational(numer: i64, denom: i64) -> Value {
+        Value::exact(BigRational::new(BigInt::from(numer), BigInt::from(denom)))
+    }
+
+    #[test]
+    fn test_value_arithmetic() {
+        let inputs = vec![
+            (Value::from(2) + Value::from(3), Value::from(5)),
+            (Value::from(2) - Value::from(3), Value::from(-1)),
+            (Value::from(6) * Value::from(7), Value::from(42)),
+            (Value::from(20) / Value::from(5), Value::from(4)),
+            (Value::from(1) / Value::from(4), rational(1, 4)),
+            (rational(1, 3) + rational(1, 6), rational(1, 2)),
+            (rational(1, 3) * Value::from(3), Value::from(1)),
+            (rational(1, 2) + Value::from(0.25), Value::from(0.75)),
+            (Value::from(2) + Value::from(0.5), Value::from(2.5)),
+            (-Value::from(3), Value::from(-3)),
+            (-rational(1, 3), rational(-1, 3)),
+            (
+                integer("9223372036854775807") + Value::from(1),
+                integer("9223372036854775808"),
+            ),
+            (
+                integer("4294967296") * integer("4294967296"),
+                integer("18446744073709551616"),
+            ),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0, i.1);
+        }
+    }
+
+    #[test]
+    fn test_value_exact() {
+        assert_eq!(rational(6, 3), Value::from(2));
+        assert!(matches!(rational(1, 3), Value::Rational(_)));
+    }
+
+    #[test]
+    fn test_value_parse_decimal() {
+        let inputs = vec![
+            ("12", Some(Value::from(12))),
+            ("2.5", Some(rational(5, 2))),
+            (".5", Some(rational(1, 2))),
+            ("3.", Some(Value::from(3))),
+            ("0.10", Some(rational(1, 10))),
+            (".", None),
+            ("1.2.3", None),
+        ];
+
+        for i in inputs {
+            assert_eq!(Value::parse_decimal(i.0), i.1, "{}", i.0);
+        }
+    }
+
+    fn decimal(s: &str) -> Value {
+        Value::Decimal(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_value_decimal_arithmetic() {
+        let inputs = vec![
+            (decimal("1.10") * Value::from(3), decimal("3.30")),
+            (decimal("0.1") + decimal("0.2"), decimal("0.3")),
+            (Value::from(1) - decimal("0.01"), decimal("0.99")),
+            (decimal("10") / Value::from(4), decimal("2.5")),
+            (rational(1, 4) + decimal("1"), decimal("1.25")),
+            (decimal("7.5").modulo(Value::from(2)), decimal("1.5")),
+            (decimal("-7").modulo(Value::from(3)), decimal("2")),
+            (decimal("1.5").pow(decimal("2")), decimal("2.25")),
+            (decimal("2").pow(Value::from(-2)), decimal("0.25")),
+            (-decimal("1.5"), decimal("-1.5")),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0, i.1);
+        }
+
+        // Overflowing the decimal range falls back to floating point
+        let max = Value::Decimal(Decimal::MAX);
+        assert_eq!(
+            max.clone() * max,
+            Value::from(Decimal::MAX.to_f64().unwrap().powi(2))
+        );
+    }
+
+    #[test]
+    fn test_value_round() {
+        let decimal_arithmetic = |precision, rounding| Arithmetic::Decimal {
+            precision,
+            rounding,
+        };
+
+        let inputs = vec![
+            ("2.345", 2, Rounding::HalfEven, "2.34"),
+            ("2.355", 2, Rounding::HalfEven, "2.36"),
+            ("2.345", 2, Rounding::HalfUp, "2.35"),
+            ("-2.345", 2, Rounding::HalfUp, "-2.35"),
+            ("2.349", 2, Rounding::Truncate, "2.34"),
+            ("-2.349", 2, Rounding::Truncate, "-2.34"),
+            ("3.3", 2, Rounding::HalfEven, "3.30"),
+            ("7", 0, Rounding::HalfEven, "7"),
+            ("1.5", 0, Rounding::HalfEven, "2"),
+        ];
+
+        for i in inputs {
+            let value = decimal(i.0).round(decimal_arithmetic(i.1, i.2));
+            assert_eq!(value.to_string(), i.3, "{} {:?}", i.0, i.2);
+        }
+
+        assert_eq!(rational(1, 2).round(Arithmetic::Exact), rational(1, 2));
+    }
+
+    #[test]
+    fn test_value_with_arithmetic() {
+        let decimal_arithmetic = Arithmetic::Decimal {
+            precision: 2,
+            rounding: Rounding::HalfEven,
+        };
+
+        let inputs = vec![
+            (Value::from(3), decimal("3")),
+            (rational(11, 10), decimal("1.1")),
+            (rational(1, 3), decimal("0.3333333333333333333333333333")),
+            (Value::from(0.5), decimal("0.5")),
+            (Value::from(f64::INFINITY), Value::from(f64::INFINITY)),
+            (
+                integer("10").pow(Value::from(30)),
+                integer("10").pow(Value::from(30)),
+            ),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.with_arithmetic(decimal_arithmetic), i.1);
+        }
+
+        assert_eq!(
+            rational(1, 3).with_arithmetic(Arithmetic::Exact),
+            rational(1, 3)
+        );
+    }
+
+    #[test]
+    fn test_value_to_exact() {
+        let inputs = vec![
+            (Value::from(3), Some(rational(3, 1))),
+            (rational(1, 3), Some(rational(1, 3))),
+            (decimal("3.30"), Some(rational(33, 10))),
+            (decimal("-0.125"), Some(rational(-1, 8))),
+            (Value::from(0.5), None),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.to_exact().map(Value::exact), i.1);
+        }
+    }
+
+    #[test]
+    fn test_value_parse_radix() {
+        let inputs = vec![
+            ("FF", 16, Some(Value::from(255))),
+            ("ff", 16, Some(Value::from(255))),
+            ("777", 8, Some(Value::from(511))),
+            ("1010", 2, Some(Value::from(10))),
+            (
+                "FFFFFFFFFFFFFFFFFFFF",
+                16,
+                Some(integer("1208925819614629174706175")),
+            ),
+            ("", 16, None),
+            ("12", 2, None),
+            ("1_0", 2, None),
+            ("G", 16, None),
+        ];
+
+        for i in inputs {
+            assert_eq!(Value::parse_radix(i.0, i.1), i.2, "{} {}", i.0, i.1);
+        }
+    }
+
+    #[test]
+    fn test_value_integer_arithmetic() {
+        let inputs = vec![
+            (Value::from(7), Value::from(7)),
+            (rational(7, 2), Value::from(3)),
+            (rational(-7, 2), Value::from(-3)),
+            (decimal("2.99"), Value::from(2)),
+            (Value::from(-2.5), Value::from(-2)),
+            (Value::from(1e20), integer("100000000000000000000")),
+            (Value::from(f64::INFINITY), Value::from(f64::INFINITY)),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.with_arithmetic(Arithmetic::Integer { word: None }), i.1);
+        }
+    }
+
+    #[test]
+    fn test_value_modulo() {
+        let inputs = vec![
+            (Value::from(7), Value::from(3), Value::from(1)),
+            (Value::from(-7), Value::from(3), Value::from(2)),
+            (Value::from(7), Value::from(-3), Value::from(-2)),
+            (rational(15, 2), Value::from(2), rational(3, 2)),
+            (Value::from(7.5), Value::from(2), Value::from(1.5)),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.modulo(i.1), i.2);
+        }
+    }
+
+    #[test]
+    fn test_value_pow() {
+        let inputs = vec![
+            (Value::from(2), Value::from(10), Value::from(1024)),
+            (Value::from(0), Value::from(0), Value::from(1)),
+            (Value::from(2), Value::from(-1), rational(1, 2)),
+            (rational(2, 3), Value::from(2), rational(4, 9)),
+            (rational(2, 3), Value::from(-2), rational(9, 4)),
+            (Value::from(4), rational(1, 2), Value::from(2.0)),
+            (
+                Value::from(2),
+                Value::from(64),
+                integer("18446744073709551616"),
+            ),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.pow(i.1), i.2);
+        }
+
+        // Results beyond the exact limit fall back to floating point
+        let result = Value::from(10).pow(Value::from(1_000_000));
+        assert_eq!(result, Value::from(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_integer_factorial() {
+        let inputs = vec![
+            (0, Some(1i64)),
+            (1, Some(1)),
+            (5, Some(120)),
+            (-5, Some(-120)),
+            (20, Some(2432902008176640000)),
+        ];
+
+        for i in inputs {
+            assert_eq!(integer_factorial(&BigInt::from(i.0)), i.1.map(BigInt::from));
+        }
+
+        assert_eq!(integer_factorial(&BigInt::from(10_001)), None);
+    }
+
+    #[test]
+    fn test_value_display() {
+        let inputs = vec![
+            (Value::from(42), "42"),
+            (integer("-18446744073709551616"), "-18446744073709551616"),
+            (rational(1, 2), "0.5"),
+            (rational(-1, 8), "-0.125"),
+            (rational(3, 10), "0.3"),
+            (rational(1, 3), "0.3333333333333333"),
+            (Value::from(2.5), "2.5"),
+            (Value::from(3.0), "3"),
+            (Value::from(1e300), "1e300"),
+            (decimal("3.30"), "3.30"),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.to_string(), i.1);
+        }
+    }
+
+    #[test]
+    fn test_value_fraction() {
+        let inputs = vec![
+            (rational(1, 2), Some("1/2")),
+            (rational(-7, 3), Some("-7/3")),
+            (Value::from(4), None),
+            (Value::from(0.5), None),
+            (decimal("0.5"), None),
+        ];
+
+        for i in inputs {
+            assert_eq!(i.0.fraction().as_deref(), i.1);
+        }
+    }
+}
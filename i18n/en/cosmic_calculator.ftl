@@ -2,6 +2,33 @@ app-title = Cosmic Calculator
 about = About
 repository = Repository
 view = View
+file = File
+import-history = Import history…
+export-history = Export history
+export-csv = Export as CSV…
+export-json = Export as JSON…
+export-markdown = Export as Markdown…
+history-file-name = calculator-history
 basic = Basic
 advanced = Advanced
 developer = Developer
+history = History
+clear-history = Clear history
+search-history = Search history
+no-history = No calculations found
+pin = Pin to the top
+rerun = Calculate again
+variables = Variables
+functions = Functions
+no-variables = No variables or functions defined
+clear-variables = Clear all
+show-fractions = Show fractions
+cycle-notation = Change number format
+notation-auto = Auto
+notation-fixed = Fix
+notation-significant = Sig
+notation-scientific = Sci
+notation-engineering = Eng
+signed = Signed
+unsigned = Unsigned
+overflow = Overflow
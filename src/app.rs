@@ -1,21 +1,46 @@
 // SPDX-License-Identifier: MIT
 
 use crate::config::Config;
+use crate::export::{self, ExportFormat};
 use crate::fl;
-use calclib::evaluator::evaluate;
-use calclib::validator::validate;
+use crate::state::{HistoryEntry, State};
+use calclib::context::{AngleUnit, Arithmetic, EvaluationContext, Radix, Word};
+use calclib::environment::{self, Environment};
+use calclib::evaluator::EvaluationResult;
+use calclib::format::{Format, Notation};
+use calclib::locale::Locale;
+use calclib::parser::{self, ParseStatus, TokenKind};
+use calclib::validator::validate_localized;
 use cosmic::app::context_drawer;
-use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use cosmic::cosmic_config::{self, ConfigSet, CosmicConfigEntry};
+use cosmic::dialog::file_chooser::{self, FileFilter};
 use cosmic::iced::alignment::{Horizontal, Vertical};
-use cosmic::iced::{clipboard, Alignment, Color, Length, Padding};
+use cosmic::iced::keyboard::{Key, Modifiers, key::Named};
+use cosmic::iced::{
+    Alignment, Color, Event, Length, Padding, Subscription, clipboard, event, keyboard, mouse,
+};
 use cosmic::prelude::*;
+use cosmic::widget::menu::key_bind::{KeyBind, Modifier};
 use cosmic::widget::{self, Id, about::About, button, icon, menu, nav_bar, svg, text, text_input};
 use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 const APP_ICON: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
 const INPUT_ID: &str = "calculator-input";
 const HISTORY_ID: &str = "history-scrollable";
+const ERROR_COLOR: Color = Color::from_rgb(0.93, 0.33, 0.33);
+const PREVIEW_COLOR: Color = Color::from_rgb(0.55, 0.55, 0.55);
+/// Decimal places of results in decimal arithmetic when config does not set them.
+const DEFAULT_PRECISION: u32 = 2;
+/// The longest time between the clicks of a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// The fewest bits the bit grid shows, whatever the word size.
+const GRID_BITS: usize = 64;
+/// The number of calculations kept in the history unless configured otherwise.
+const DEFAULT_HISTORY_LENGTH: usize = 100;
 
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
@@ -34,12 +59,62 @@ pub struct AppModel {
     config: Config,
     /// Handle to the config context for persisting changes.
     config_handler: Option<cosmic_config::Config>,
-    /// Calculator history (expression, result) pairs
-    history: Vec<(String, String)>,
+    /// Handle to the state context for persisting the history.
+    state_handler: Option<cosmic_config::Config>,
+    /// Calculator history
+    history: Vec<HistoryEntry>,
+    /// Text the history drawer is filtered by
+    history_search: String,
+    /// The history entry whose expression was last recalled into the input with the arrow keys
+    recalled: Option<usize>,
+    /// Whether text of the input is selected, as far as can be told from the keys and clicks that
+    /// select it, so that Ctrl+C copies the selection rather than the result
+    input_selected: bool,
+    /// When the left mouse button was last pressed, to tell double clicks
+    last_click: Option<Instant>,
     /// Calculator input
     input: String,
     /// Calculator result
     result: String,
+    /// The result of the input as it is typed, shown dimmed in place of the result. Empty while
+    /// the input is incomplete or invalid, and `None` when the input is empty or was just
+    /// evaluated.
+    preview: Option<String>,
+    /// Counts changes to the input, so that a preview finished after a later change is discarded
+    preview_generation: u64,
+    /// Whether a preview is being evaluated. Only one runs at a time, so that typing an
+    /// expensive expression such as `9999!` does not start an evaluation for every key.
+    previewing: bool,
+    /// Whether the input changed while a preview was being evaluated, so that it is previewed
+    /// once that evaluation finishes
+    preview_queued: bool,
+    /// Whether the preview is an error, which is hidden as soon as the input changes
+    preview_error: bool,
+    /// The input with the parentheses it leaves open closed, worked out as the input changes.
+    /// `None` while it is missing an operand, which disables the `=` key.
+    closed_input: Option<String>,
+    /// The last value, kept to write the result again when the format changes
+    value: Option<EvaluationResult>,
+    /// Options used to write results
+    format: Format,
+    /// Label of the key that types the decimal separator of the locale
+    decimal_key: String,
+    /// Calculator result as a fraction, if it is exact but not an integer
+    fraction: Option<String>,
+    /// Show exact results as fractions rather than decimals
+    show_fractions: bool,
+    /// Byte range of the input responsible for the last evaluation error
+    error_span: Option<Range<usize>>,
+    /// Options passed to calclib when evaluating the input
+    context: EvaluationContext,
+    /// The radix of numbers typed on the Developer page
+    radix: Radix,
+    /// The width and signedness of integers on the Developer page
+    word: Word,
+    /// Show the alternative functions, such as the inverses, on the Advanced keypad
+    shift: bool,
+    /// Variables assigned during this session, including `ans`, and user-defined functions
+    environment: Environment,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -48,9 +123,40 @@ pub enum Message {
     InputChanged(String),
     KeyPressed(String),
     ModeSelected(String),
+    AngleUnitSelected(AngleUnit),
+    RadixSelected(Radix),
+    WordSizeSelected(u32),
+    ToggleSigned,
+    ToggleBit(usize),
+    ToggleShift,
+    HistorySearchChanged(String),
+    LoadHistoryEntry(usize),
+    RerunHistoryEntry(usize),
+    RemoveHistoryEntry(usize),
+    TogglePinned(usize),
+    RecallPrevious,
+    RecallNext,
+    ClearInput,
+    ClearHistory,
+    Export(ExportFormat),
+    ExportTo(ExportFormat, Option<PathBuf>),
+    Import,
+    ImportFrom(Option<PathBuf>),
+    CopyResult,
+    CopyExpression,
+    Key(Modifiers, Key),
+    PointerPressed,
+    PointerDragged,
+    Insert(String),
+    ToggleFractions,
+    CycleNotation,
     CopyResultToInput(String),
+    PreviewReady(u64, Option<Result<String, String>>),
     LaunchUrl(String),
     ToggleContextPage(ContextPage),
+    RemoveVariable(String),
+    RemoveFunction(String),
+    ClearVariables,
     // UpdateConfig(Config),
 }
 
@@ -60,7 +166,7 @@ impl cosmic::Application for AppModel {
     type Executor = cosmic::executor::Default;
 
     /// Data that your application receives to its init method.
-    type Flags = ();
+    type Flags = Locale;
 
     /// Messages which the application and its widgets will emit.
     type Message = Message;
@@ -79,7 +185,7 @@ impl cosmic::Application for AppModel {
     /// Initializes the application with any given flags and startup commands.
     fn init(
         core: cosmic::Core,
-        _flags: Self::Flags,
+        locale: Self::Flags,
     ) -> (Self, Task<cosmic::Action<Self::Message>>) {
         // Create a nav bar with three page items.
         let mut nav = nav_bar::Model::default();
@@ -111,6 +217,19 @@ impl cosmic::Application for AppModel {
                 Err(_) => (Config::default(), None),
             };
 
+        // Load the history from disk.
+        let (state, state_handler) =
+            match cosmic_config::Config::new_state(Self::APP_ID, State::VERSION) {
+                Ok(context) => {
+                    let state = match State::get_entry(&context) {
+                        Ok(state) => state,
+                        Err((_errors, state)) => state,
+                    };
+                    (state, Some(context))
+                }
+                Err(_) => (State::default(), None),
+            };
+
         // Activate the saved page from config.
         if let Some(page) = Page::from_str(&config.page) {
             let target = nav.iter().find(|&id| {
@@ -123,18 +242,82 @@ impl cosmic::Application for AppModel {
             }
         }
 
+        let arithmetic = if config.decimal {
+            Arithmetic::Decimal {
+                precision: config.precision.unwrap_or(DEFAULT_PRECISION),
+                rounding: config.rounding.parse().unwrap_or_default(),
+            }
+        } else {
+            Arithmetic::Exact
+        };
+
+        let context = EvaluationContext {
+            angle_unit: config.angle_unit.parse().unwrap_or_default(),
+            arithmetic,
+            locale,
+            ..Default::default()
+        };
+
+        // Define the functions saved in config.
+        let mut environment = Environment::new();
+        for definition in &config.functions {
+            let _ = environment.evaluate(definition.clone(), &context);
+        }
+
+        let show_fractions = config.fractions;
+
+        let defaults = Format::for_locale(&locale);
+        let format = Format {
+            notation: config.notation.parse().unwrap_or_default(),
+            decimals: config.decimals.unwrap_or(defaults.decimals),
+            significant: config.significant.unwrap_or(defaults.significant),
+            grouping: config.grouping,
+            ..defaults
+        };
+
+        let radix = config.radix.parse().unwrap_or_default();
+        let word = Word {
+            bits: config
+                .word_size
+                .filter(|bits| Word::SIZES.contains(bits))
+                .unwrap_or(Word::default().bits),
+            signed: !config.unsigned,
+        };
+
         // Construct the app model with the runtime's core.
         let mut app = AppModel {
             core,
             context_page: ContextPage::default(),
             about,
             nav,
-            key_binds: HashMap::new(),
+            key_binds: key_binds(),
             config,
             config_handler,
-            history: Vec::new(),
+            state_handler,
+            history: state.history,
+            history_search: String::new(),
+            recalled: None,
+            input_selected: false,
+            last_click: None,
             input: "".to_string(),
             result: "0".to_string(),
+            preview: None,
+            preview_generation: 0,
+            previewing: false,
+            preview_queued: false,
+            preview_error: false,
+            closed_input: None,
+            value: None,
+            format,
+            decimal_key: locale.decimal_separator.to_string(),
+            fraction: None,
+            show_fractions,
+            error_span: None,
+            context,
+            radix,
+            word,
+            shift: false,
+            environment,
         };
 
         // Create a startup command that sets the window title.
@@ -145,17 +328,66 @@ impl cosmic::Application for AppModel {
 
     /// Elements to pack at the start of the header bar.
     fn header_start(&self) -> Vec<Element<'_, Self::Message>> {
-        let menu_bar = menu::bar(vec![menu::Tree::with_children(
-            menu::root(fl!("view")).apply(Element::from),
-            menu::items(
-                &self.key_binds,
-                vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+        let export_items = ExportFormat::ALL.into_iter().map(|format| {
+            let label = match format {
+                ExportFormat::Csv => fl!("export-csv"),
+                ExportFormat::Json => fl!("export-json"),
+                ExportFormat::Markdown => fl!("export-markdown"),
+            };
+            menu::Item::Button(label, None, MenuAction::Export(format))
+        });
+
+        let menu_bar = menu::bar(vec![
+            menu::Tree::with_children(
+                menu::root(fl!("file")).apply(Element::from),
+                menu::items(
+                    &self.key_binds,
+                    std::iter::once(menu::Item::Button(
+                        fl!("import-history"),
+                        None,
+                        MenuAction::Import,
+                    ))
+                    .chain(std::iter::once(menu::Item::Divider))
+                    .chain(export_items)
+                    .collect(),
+                ),
             ),
-        )]);
+            menu::Tree::with_children(
+                menu::root(fl!("view")).apply(Element::from),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        menu::Item::Button(fl!("history"), None, MenuAction::History),
+                        menu::Item::Button(fl!("clear-history"), None, MenuAction::ClearHistory),
+                        menu::Item::Button(fl!("variables"), None, MenuAction::Variables),
+                        menu::Item::Button(fl!("about"), None, MenuAction::About),
+                    ],
+                ),
+            ),
+        ]);
 
         vec![menu_bar.into()]
     }
 
+    /// Register subscriptions for this application.
+    fn subscription(&self) -> Subscription<Self::Message> {
+        // Pass key presses to the key bindings, including those typed into the input, and the
+        // pointer events that select text in the input
+        event::listen_with(|event, status, _window| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                Some(Message::Key(modifiers, key))
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                Some(Message::PointerPressed)
+            }
+            // The input captures the pointer only while text is selected by dragging
+            Event::Mouse(mouse::Event::CursorMoved { .. }) if status == event::Status::Captured => {
+                Some(Message::PointerDragged)
+            }
+            _ => None,
+        })
+    }
+
     /// Enables the COSMIC application to create a nav bar with this model.
     // fn nav_model(&self) -> Option<&nav_bar::Model> {
     //     Some(&self.nav)
@@ -173,6 +405,16 @@ impl cosmic::Application for AppModel {
                 |url| Message::LaunchUrl(url.to_string()),
                 Message::ToggleContextPage(ContextPage::About),
             ),
+            ContextPage::Variables => context_drawer::context_drawer(
+                self.variables(),
+                Message::ToggleContextPage(ContextPage::Variables),
+            )
+            .title(fl!("variables")),
+            ContextPage::History => context_drawer::context_drawer(
+                self.history_drawer(),
+                Message::ToggleContextPage(ContextPage::History),
+            )
+            .title(fl!("history")),
         })
     }
 
@@ -187,10 +429,13 @@ impl cosmic::Application for AppModel {
         let history_items: Vec<Element<'_, Self::Message>> = self
             .history
             .iter()
-            .map(|(expr, result)| {
-                widget::row::with_capacity(2)
+            .map(|entry| {
+                let result = entry.display(self.show_fractions);
+
+                widget::row::with_capacity(3)
+                    .push(text(entry.time()).size(10))
                     .push(
-                        text(format!("{} = {}", expr, result))
+                        text(entry.describe(self.show_fractions))
                             .size(14)
                             .width(Length::Fill)
                             .align_x(Horizontal::Right),
@@ -198,7 +443,10 @@ impl cosmic::Application for AppModel {
                     .push(widget::tooltip(
                         button::icon(icon::from_name("edit-copy-symbolic").size(14))
                             .extra_small()
-                            .on_press(Message::CopyResultToInput(result.clone())),
+                            .on_press_maybe(
+                                (!result.is_empty())
+                                    .then(|| Message::CopyResultToInput(result.to_string())),
+                            ),
                         text("Copy to input"),
                         widget::tooltip::Position::Left,
                     ))
@@ -275,8 +523,8 @@ impl cosmic::Application for AppModel {
             .push(
                 widget::row::with_capacity(5)
                     .push(make_button("0", None))
-                    .push(make_button(".", None))
-                    .push(make_button("=", None))
+                    .push(make_button(&self.decimal_key, None))
+                    .push(self.equals_button())
                     .push(make_button("+", None))
                     .push(make_button("Ans", None))
                     .spacing(space_s),
@@ -304,13 +552,72 @@ impl cosmic::Application for AppModel {
             .spacing(space_s)
             .into();
 
-        let result = widget::row::with_capacity(1)
+        // Echo the input with the part responsible for the last error highlighted
+        let error_marker = self.error_span.clone().and_then(|range| {
+            let before = self.input.get(..range.start)?;
+            let marked = self.input.get(range.clone())?;
+            let after = self.input.get(range.end..)?;
+
+            Some(
+                widget::row::with_capacity(3)
+                    .push(text(before).size(14))
+                    .push(
+                        text(if marked.is_empty() { "_" } else { marked })
+                            .size(14)
+                            .font(cosmic::font::bold())
+                            .class(cosmic::theme::Text::Color(ERROR_COLOR)),
+                    )
+                    .push(text(after).size(14)),
+            )
+        });
+
+        let result_text = match &self.preview {
+            Some(preview) => {
+                text(preview.as_str()).class(cosmic::theme::Text::Color(PREVIEW_COLOR))
+            }
+            None => text(self.displayed_result()),
+        };
+
+        let fraction_toggle = if self.show_fractions {
+            button::suggested("a/b")
+        } else {
+            button::standard("a/b")
+        }
+        .on_press(Message::ToggleFractions);
+
+        let notation_toggle =
+            button::standard(notation_label(self.format.notation)).on_press(Message::CycleNotation);
+
+        // Mark a result that wrapped around the word size
+        let overflow = self
+            .value
+            .as_ref()
+            .filter(|value| value.overflowed())
+            .map(|_| {
+                text(fl!("overflow"))
+                    .size(14)
+                    .class(cosmic::theme::Text::Color(ERROR_COLOR))
+            });
+
+        let result = widget::row::with_capacity(5)
+            .push_maybe(error_marker)
+            .push_maybe(overflow)
             .push(
-                text(self.result.as_str())
+                result_text
                     .size(24)
                     .width(Length::Fill)
                     .align_x(Horizontal::Right),
             )
+            .push(widget::tooltip(
+                notation_toggle,
+                text(fl!("cycle-notation")),
+                widget::tooltip::Position::Left,
+            ))
+            .push(widget::tooltip(
+                fraction_toggle,
+                text(fl!("show-fractions")),
+                widget::tooltip::Position::Left,
+            ))
             .align_y(Alignment::End)
             .spacing(space_s);
 
@@ -327,13 +634,119 @@ impl cosmic::Application for AppModel {
                 .into(),
 
             Page::Advanced => {
-                let header = widget::row::with_capacity(2)
-                    .push(widget::text::title2(fl!("advanced")))
-                    .align_y(Alignment::End)
+                let angle_units = [
+                    ("DEG", AngleUnit::Degrees),
+                    ("RAD", AngleUnit::Radians),
+                    ("GRAD", AngleUnit::Gradians),
+                ]
+                .into_iter()
+                .fold(widget::row::with_capacity(3), |row, (label, unit)| {
+                    let button = if self.context.angle_unit == unit {
+                        button::suggested(label)
+                    } else {
+                        button::standard(label)
+                    };
+                    row.push(button.on_press(Message::AngleUnitSelected(unit)))
+                })
+                .spacing(space_s);
+
+                // The 2nd key swaps the functions of the keys with two labels
+                let shift = key_button("2nd")
+                    .class(if self.shift {
+                        cosmic::theme::Button::Suggested
+                    } else {
+                        cosmic::theme::Button::Standard
+                    })
+                    .on_press(Message::ToggleShift);
+
+                let scientific_keyboard = widget::column::with_capacity(4)
+                    .push(
+                        widget::row::with_capacity(5)
+                            .push(shift)
+                            .push(make_shift_button(
+                                self.shift,
+                                ("sin", "sin("),
+                                ("sin⁻¹", "asin("),
+                            ))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("cos", "cos("),
+                                ("cos⁻¹", "acos("),
+                            ))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("tan", "tan("),
+                                ("tan⁻¹", "atan("),
+                            ))
+                            .push(make_button("π", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(5)
+                            .push(make_button("EE", Some(Message::Insert("×10^".to_string()))))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("sinh", "sinh("),
+                                ("sinh⁻¹", "asinh("),
+                            ))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("cosh", "cosh("),
+                                ("cosh⁻¹", "acosh("),
+                            ))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("tanh", "tanh("),
+                                ("tanh⁻¹", "atanh("),
+                            ))
+                            .push(make_button("e", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(5)
+                            .push(make_shift_button(self.shift, ("x²", "^2"), ("x³", "^3")))
+                            .push(make_button("xʸ", Some(Message::Insert("^".to_string()))))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("√x", "sqrt("),
+                                ("∛x", "cbrt("),
+                            ))
+                            .push(make_button(
+                                "ʸ√x",
+                                Some(Message::Insert("^(1÷".to_string())),
+                            ))
+                            .push(make_button(
+                                "1/x",
+                                Some(Message::Insert("^(−1)".to_string())),
+                            ))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(5)
+                            .push(make_shift_button(self.shift, ("10ˣ", "10^"), ("2ˣ", "2^")))
+                            .push(make_button("eˣ", Some(Message::Insert("exp(".to_string()))))
+                            .push(make_shift_button(
+                                self.shift,
+                                ("log", "log("),
+                                ("log₂", "log2("),
+                            ))
+                            .push(make_button("ln", Some(Message::Insert("ln(".to_string()))))
+                            .push(make_button(
+                                "|x|",
+                                Some(Message::Insert("abs(".to_string())),
+                            ))
+                            .spacing(space_s),
+                    )
                     .spacing(space_s);
 
-                widget::column::with_capacity(1)
-                    .push(header)
+                widget::column::with_capacity(8)
+                    .push(history)
+                    .push(input)
+                    .push(result)
+                    .push(angle_units)
+                    .push(scientific_keyboard)
+                    .push(basic_keyboard)
+                    .push(widget::vertical_space().height(25))
                     .push(calculator_mode)
                     .spacing(space_s)
                     .height(Length::Fill)
@@ -341,13 +754,139 @@ impl cosmic::Application for AppModel {
             }
 
             Page::Developer => {
-                let header = widget::row::with_capacity(2)
-                    .push(widget::text::title1(fl!("developer")))
-                    .align_y(Alignment::End)
+                // The result in every radix, with a button selecting the radix of typed numbers
+                let radixes = Radix::ALL
+                    .into_iter()
+                    .fold(widget::column::with_capacity(4), |column, radix| {
+                        let label = radix.as_str().to_uppercase();
+                        let button = if self.radix == radix {
+                            button::suggested(label)
+                        } else {
+                            button::standard(label)
+                        };
+                        let digits = self
+                            .value
+                            .as_ref()
+                            .and_then(|value| value.to_radix(radix, Some(self.word)))
+                            .unwrap_or_default();
+
+                        column.push(
+                            widget::row::with_capacity(2)
+                                .push(button.on_press(Message::RadixSelected(radix)))
+                                .push(
+                                    text(digits)
+                                        .size(14)
+                                        .width(Length::Fill)
+                                        .align_x(Horizontal::Right),
+                                )
+                                .align_y(Alignment::Center)
+                                .spacing(space_s),
+                        )
+                    })
+                    .spacing(4);
+
+                // The width of integers, and whether they are signed
+                let signedness = if self.word.signed {
+                    fl!("signed")
+                } else {
+                    fl!("unsigned")
+                };
+                let word_sizes = Word::SIZES
+                    .into_iter()
+                    .fold(widget::row::with_capacity(6), |row, bits| {
+                        let label = bits.to_string();
+                        let button = if self.word.bits == bits {
+                            button::suggested(label)
+                        } else {
+                            button::standard(label)
+                        };
+                        row.push(button.on_press(Message::WordSizeSelected(bits)))
+                    })
+                    .push(widget::horizontal_space())
+                    .push(button::standard(signedness).on_press(Message::ToggleSigned))
                     .spacing(space_s);
 
-                widget::column::with_capacity(1)
-                    .push(header)
+                let developer_keyboard = widget::column::with_capacity(6)
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_digit_button("A", self.radix))
+                            .push(make_digit_button("B", self.radix))
+                            .push(make_digit_button("7", self.radix))
+                            .push(make_digit_button("8", self.radix))
+                            .push(make_digit_button("9", self.radix))
+                            .push(make_button("÷", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_digit_button("C", self.radix))
+                            .push(make_digit_button("D", self.radix))
+                            .push(make_digit_button("4", self.radix))
+                            .push(make_digit_button("5", self.radix))
+                            .push(make_digit_button("6", self.radix))
+                            .push(make_button("×", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_digit_button("E", self.radix))
+                            .push(make_digit_button("F", self.radix))
+                            .push(make_digit_button("1", self.radix))
+                            .push(make_digit_button("2", self.radix))
+                            .push(make_digit_button("3", self.radix))
+                            .push(make_button("−", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_button("(", None))
+                            .push(make_button(")", None))
+                            .push(make_digit_button("0", self.radix))
+                            .push(make_button("^", None))
+                            .push(self.equals_button())
+                            .push(make_button("+", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_button("&", None))
+                            .push(make_button("|", None))
+                            .push(make_button(
+                                "xor",
+                                Some(Message::Insert(" xor ".to_string())),
+                            ))
+                            .push(make_button("~", None))
+                            .push(make_button("<<", None))
+                            .push(make_button(">>", None))
+                            .spacing(space_s),
+                    )
+                    .push(
+                        widget::row::with_capacity(6)
+                            .push(make_button("AC", None))
+                            .push(make_button("C", None))
+                            .push(make_button("⌫", None))
+                            .push(make_button("Ans", None))
+                            .push(make_button(
+                                "rol",
+                                Some(Message::Insert(" rol ".to_string())),
+                            ))
+                            .push(make_button(
+                                "ror",
+                                Some(Message::Insert(" ror ".to_string())),
+                            ))
+                            .spacing(space_s),
+                    )
+                    .spacing(space_s);
+
+                widget::column::with_capacity(9)
+                    .push(history)
+                    .push(input)
+                    .push(result)
+                    .push(radixes)
+                    .push(word_sizes)
+                    .push(self.bit_grid())
+                    .push(developer_keyboard)
+                    .push(widget::vertical_space().height(25))
                     .push(calculator_mode)
                     .spacing(space_s)
                     .height(Length::Fill)
@@ -374,34 +913,51 @@ impl cosmic::Application for AppModel {
         match message {
             Message::InputChanged(value) => {
                 println!("input changed: {}", value);
-
-                if value.chars().any(|c| c == '=' || c == '\n') {
+                self.error_span = None;
+                self.recalled = None;
+
+                // A newly typed `=` asks for the result unless it starts an assignment: "x="
+                let typed_equals = value.matches('=').count() > self.input.matches('=').count();
+                if value.contains('\n')
+                    || (typed_equals && !is_assignment(&value, &self.context.locale))
+                {
                     return self.evaluate_input();
                 }
 
-                if value.chars().all(|c| validate(&c)) {
+                if value
+                    .chars()
+                    .all(|c| validate_localized(&c, &self.context.locale))
+                {
                     self.input = substitute(value);
+                    return self.update_preview();
                 }
             }
             Message::CopyResultToInput(result) => {
                 self.input.push_str(&result);
                 return Task::batch([
+                    self.update_preview(),
                     clipboard::write(result),
                     text_input::move_cursor_to_end(Id::new(INPUT_ID)),
                 ]);
             }
             Message::KeyPressed(value) => {
                 println!("key pressed: {}", value);
+                self.error_span = None;
 
                 match value.as_str() {
-                    "AC" => {
-                        self.history.clear();
+                    // C clears only the entry. AC also resets the result and the variables of
+                    // the session, including ans, while the history is cleared from the menu
+                    "C" => {
                         self.input.clear();
-                        self.result = "0".to_string();
+                        self.recalled = None;
                     }
-                    "C" => {
+                    "AC" => {
                         self.input.clear();
+                        self.recalled = None;
                         self.result = "0".to_string();
+                        self.value = None;
+                        self.fraction = None;
+                        self.environment.clear_variables();
                     }
                     "⌫" => {
                         self.input.pop();
@@ -421,16 +977,17 @@ impl cosmic::Application for AppModel {
                         ]);
                     }
                     "Ans" => {
-                        if let Some((_, last_result)) = self.history.last() {
-                            self.input.push_str(last_result);
-                        }
+                        self.input.push_str(environment::ANS);
                     }
                     _ => {
                         self.input.push_str(&value);
                     }
                 }
 
-                return text_input::move_cursor_to_end(Id::new(INPUT_ID));
+                return Task::batch([
+                    self.update_preview(),
+                    text_input::move_cursor_to_end(Id::new(INPUT_ID)),
+                ]);
             }
             Message::ModeSelected(mode) => {
                 if let Some(page) = Page::from_str(&mode) {
@@ -447,6 +1004,283 @@ impl cosmic::Application for AppModel {
                     }
                 }
             }
+            Message::ToggleFractions => {
+                self.show_fractions = !self.show_fractions;
+
+                // Persist the fraction display to config.
+                self.config.fractions = self.show_fractions;
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+            }
+            Message::CycleNotation => {
+                self.format.notation = self.format.notation.next();
+                if let Some(ref value) = self.value {
+                    self.result = value.format(&self.format);
+                }
+
+                // Persist the notation to config.
+                self.config.notation = self.format.notation.as_str().to_string();
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+                return self.update_preview();
+            }
+            Message::RadixSelected(radix) => {
+                self.radix = radix;
+
+                // Persist the radix to config.
+                self.config.radix = radix.as_str().to_string();
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+                return self.update_preview();
+            }
+            Message::WordSizeSelected(bits) => {
+                self.word.bits = bits;
+
+                // Persist the word size to config.
+                self.config.word_size = Some(bits);
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+                return self.update_preview();
+            }
+            Message::ToggleSigned => {
+                self.word.signed = !self.word.signed;
+
+                // Persist the signedness to config.
+                self.config.unsigned = !self.word.signed;
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+                return self.update_preview();
+            }
+            Message::ToggleBit(bit) => {
+                let mut bits = self.bits();
+                if let Some(set) = bits.get_mut(bit) {
+                    *set = !*set;
+                }
+
+                // Write the edited value to the input in the selected radix
+                let value = EvaluationResult::from_bits(&bits, self.word);
+                self.input = value
+                    .to_radix(self.radix, Some(self.word))
+                    .unwrap_or_default();
+                self.result = value.format(&self.format);
+                self.fraction = None;
+                self.error_span = None;
+                self.value = Some(value);
+                self.clear_preview();
+                return text_input::move_cursor_to_end(Id::new(INPUT_ID));
+            }
+            Message::ToggleShift => {
+                self.shift = !self.shift;
+            }
+            Message::HistorySearchChanged(search) => {
+                self.history_search = search;
+            }
+            Message::LoadHistoryEntry(index) => {
+                if let Some(entry) = self.history.get(index) {
+                    self.input = entry.expression.clone();
+                    self.error_span = None;
+                    self.core.window.show_context = false;
+                    return Task::batch([
+                        self.update_preview(),
+                        text_input::move_cursor_to_end(Id::new(INPUT_ID)),
+                    ]);
+                }
+            }
+            Message::RerunHistoryEntry(index) => {
+                if let Some(entry) = self.history.get(index) {
+                    self.input = entry.expression.clone();
+                    return self.evaluate_input();
+                }
+            }
+            Message::RemoveHistoryEntry(index) => {
+                if index < self.history.len() {
+                    self.history.remove(index);
+                    self.recalled = None;
+                    self.save_history();
+                }
+            }
+            Message::TogglePinned(index) => {
+                if let Some(entry) = self.history.get_mut(index) {
+                    entry.pinned = !entry.pinned;
+                    self.save_history();
+                }
+            }
+            Message::RecallPrevious => {
+                // Step back from the last entry, stopping at the first
+                let Some(last) = self.history.len().checked_sub(1) else {
+                    return Task::none();
+                };
+                let index = self.recalled.map_or(last, |index| index.saturating_sub(1));
+
+                self.recalled = Some(index);
+                self.input = self.history[index].expression.clone();
+                return Task::batch([
+                    self.update_preview(),
+                    text_input::move_cursor_to_end(Id::new(INPUT_ID)),
+                ]);
+            }
+            Message::RecallNext => {
+                // Step forward, back to an empty input after the last entry
+                let next = self
+                    .recalled
+                    .map(|index| index + 1)
+                    .filter(|&index| index < self.history.len());
+
+                self.recalled = next;
+                self.input = next
+                    .map(|index| self.history[index].expression.clone())
+                    .unwrap_or_default();
+                return Task::batch([
+                    self.update_preview(),
+                    text_input::move_cursor_to_end(Id::new(INPUT_ID)),
+                ]);
+            }
+            Message::ClearInput => {
+                self.input.clear();
+                self.error_span = None;
+                self.recalled = None;
+                self.clear_preview();
+            }
+            Message::ClearHistory => {
+                self.history.clear();
+                self.recalled = None;
+                self.save_history();
+            }
+            Message::Export(format) => {
+                let title = fl!("export-history");
+                let file_name = format!("{}.{}", fl!("history-file-name"), format.extension());
+
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::save::Dialog::new()
+                        .title(title)
+                        .file_name(file_name);
+                    let path = match dialog.save_file().await {
+                        Ok(response) => response.url().and_then(|url| url.to_file_path().ok()),
+                        Err(file_chooser::Error::Cancelled) => None,
+                        Err(err) => {
+                            eprintln!("failed to choose a file to export to: {err}");
+                            None
+                        }
+                    };
+                    Message::ExportTo(format, path)
+                });
+            }
+            Message::ExportTo(format, Some(path)) => {
+                let exported = format
+                    .export(&self.history)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+                if let Err(err) = exported {
+                    eprintln!("failed to export the history to {path:?}: {err}");
+                }
+            }
+            Message::Import => {
+                let title = fl!("import-history");
+
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new()
+                        .title(title)
+                        .filter(FileFilter::new("JSON").extension("json"));
+                    let path = match dialog.open_file().await {
+                        Ok(response) => response.url().to_file_path().ok(),
+                        Err(file_chooser::Error::Cancelled) => None,
+                        Err(err) => {
+                            eprintln!("failed to choose a file to import: {err}");
+                            None
+                        }
+                    };
+                    Message::ImportFrom(path)
+                });
+            }
+            Message::ImportFrom(Some(path)) => {
+                // The imported history replaces the current one
+                let imported = std::fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|json| export::import(&json).map_err(|err| err.to_string()));
+                match imported {
+                    Ok(history) => {
+                        self.history = history;
+                        self.recalled = None;
+                        self.save_history();
+                    }
+                    Err(err) => eprintln!("failed to import the history from {path:?}: {err}"),
+                }
+            }
+            // The file chooser was closed without choosing a file
+            Message::ExportTo(_, None) | Message::ImportFrom(None) => {}
+            Message::CopyResult => {
+                return clipboard::write(self.displayed_result().to_string());
+            }
+            Message::CopyExpression => {
+                // The input, or the expression of the last result once the input is cleared
+                let expression = match self.history.last() {
+                    Some(entry) if self.input.is_empty() => entry.expression.clone(),
+                    _ => self.input.clone(),
+                };
+                return clipboard::write(expression);
+            }
+            Message::Key(modifiers, key) => {
+                let selected = self.input_selected;
+                self.input_selected = selection_after_key(selected, modifiers, &key);
+
+                // The input copies its selection itself, so Ctrl+C only copies the result when
+                // nothing is selected
+                if selected && is_copy(modifiers, &key) {
+                    return Task::none();
+                }
+
+                for (key_bind, action) in &self.key_binds {
+                    if key_bind.matches(modifiers, &key) {
+                        return self.update(menu::action::MenuAction::message(action));
+                    }
+                }
+            }
+            Message::PointerPressed => {
+                // A double click selects a word of the input, and a single click the cursor
+                let now = Instant::now();
+                self.input_selected = self
+                    .last_click
+                    .is_some_and(|last| now.duration_since(last) < DOUBLE_CLICK);
+                self.last_click = Some(now);
+            }
+            Message::PointerDragged => {
+                self.input_selected = true;
+            }
+            Message::Insert(text) => {
+                self.error_span = None;
+                self.input.push_str(&text);
+                return Task::batch([
+                    self.update_preview(),
+                    text_input::move_cursor_to_end(Id::new(INPUT_ID)),
+                ]);
+            }
+            Message::AngleUnitSelected(unit) => {
+                self.context.angle_unit = unit;
+
+                // Persist the angle unit to config.
+                self.config.angle_unit = unit.as_str().to_string();
+                if let Some(ref handler) = self.config_handler {
+                    let _ = self.config.write_entry(handler);
+                }
+                return self.update_preview();
+            }
+            Message::RemoveVariable(name) => {
+                self.environment.remove(&name);
+            }
+            Message::RemoveFunction(name) => {
+                self.environment.remove_function(&name);
+                self.save_functions();
+            }
+            Message::ClearVariables => {
+                // The functions are kept, as they are saved between runs
+                self.environment.clear_variables();
+                return self.update_preview();
+            }
             Message::ToggleContextPage(context_page) => {
                 if self.context_page == context_page {
                     // Close the context drawer if the toggled context page is the same.
@@ -461,6 +1295,22 @@ impl cosmic::Application for AppModel {
             //     println!("updating config: {:?}", config);
             //     self.config = config;
             // }
+            Message::PreviewReady(generation, preview) => {
+                self.previewing = false;
+
+                // The preview of input that has since changed is dropped, and the current input
+                // evaluated if it was waiting for this evaluation to finish
+                if generation != self.preview_generation {
+                    return if self.preview_queued {
+                        self.update_preview()
+                    } else {
+                        Task::none()
+                    };
+                }
+
+                self.preview_error = matches!(preview, Some(Err(_)));
+                self.preview = preview.map(|preview| preview.unwrap_or_else(|err| err));
+            }
             Message::LaunchUrl(url) => match open::that_detached(&url) {
                 Ok(()) => {}
                 Err(err) => {
@@ -484,17 +1334,163 @@ impl cosmic::Application for AppModel {
             }
         }
 
-        self.update_title()
+        Task::batch([self.update_title(), self.update_preview()])
     }
 }
 /// Substitute certain characters with their calc lib equivalents
 fn substitute(input: String) -> String {
-    input.replace('*', "×").replace('/', "÷").replace('-', "−")
+    let input = input.replace('*', "×").replace('/', "÷").replace('-', "−");
+
+    // Constant names become their symbols once the word is complete: "2pi+" -> "2π+"
+    let mut output = String::with_capacity(input.len());
+    let mut word = String::new();
+    for ch in input.chars() {
+        if ch.is_ascii_alphabetic() || ch == '_' || (ch.is_ascii_digit() && !word.is_empty()) {
+            word.push(ch);
+            continue;
+        }
+
+        output.push_str(constant_symbol(&word).unwrap_or(&word));
+        output.push(ch);
+        word.clear();
+    }
+    output.push_str(&word);
+
+    output
+}
+
+/// Whether input is an assignment in progress such as "x=" or "x=5", whose `=` must not
+/// trigger evaluation.
+fn is_assignment(input: &str, locale: &Locale) -> bool {
+    match input.split_once('=') {
+        Some((name, value)) => {
+            !value.contains('=') && environment::is_assignment_target(name, locale)
+        }
+        None => false,
+    }
+}
+
+/// The symbol inserted for a constant typed out by name.
+fn constant_symbol(name: &str) -> Option<&'static str> {
+    match name {
+        "pi" => Some("π"),
+        "tau" => Some("τ"),
+        "phi" => Some("φ"),
+        _ => None,
+    }
+}
+
+/// The short label of the result format toggle.
+fn notation_label(notation: Notation) -> String {
+    match notation {
+        Notation::Auto => fl!("notation-auto"),
+        Notation::Fixed => fl!("notation-fixed"),
+        Notation::Significant => fl!("notation-significant"),
+        Notation::Scientific => fl!("notation-scientific"),
+        Notation::Engineering => fl!("notation-engineering"),
+    }
+}
+
+/// Whether key with modifiers is Ctrl+C, which copies the selection of the input or the result.
+fn is_copy(modifiers: Modifiers, key: &Key) -> bool {
+    modifiers.control() && !modifiers.shift() && key.as_ref() == Key::Character("c")
+}
+
+/// Whether text of the input is selected after a key press, given whether it was before. Keys
+/// that select text leave it selected, modifiers on their own and Ctrl+C keep the selection, and
+/// any other key removes it.
+fn selection_after_key(selected: bool, modifiers: Modifiers, key: &Key) -> bool {
+    match key.as_ref() {
+        Key::Named(Named::Shift | Named::Control | Named::Alt | Named::Super) => selected,
+        _ if is_copy(modifiers, key) => selected,
+        Key::Character("a") => modifiers.control(),
+        Key::Named(Named::ArrowLeft | Named::ArrowRight | Named::Home | Named::End) => {
+            modifiers.shift()
+        }
+        _ => false,
+    }
+}
+
+/// The input with the parentheses it leaves open closed, so that `2×(3+4` is evaluated as
+/// `2×(3+4)`, or `None` while it is missing an operand, as in `3−`. Input that cannot be read is
+/// returned unchanged, for evaluating it to point out the error.
+fn close_parens(input: &str, status: &ParseStatus) -> Option<String> {
+    match status {
+        ParseStatus::Incomplete {
+            expected,
+            open_parens,
+        } if expected.contains(&TokenKind::CloseParen) => {
+            Some(format!("{input}{}", ")".repeat(*open_parens)))
+        }
+        ParseStatus::Incomplete { .. } => None,
+        _ => Some(input.to_string()),
+    }
+}
+
+/// Input with the operator symbols of the keypad replaced by those calclib reads.
+fn to_expression(input: &str) -> String {
+    input.replace('×', "*").replace('÷', "/").replace('−', "-")
+}
+
+/// Maps a byte range of the normalized expression passed to calclib back onto the displayed
+/// input, whose operator symbols are multi-byte characters.
+fn input_range(input: &str, expression: &str, span: Range<usize>) -> Range<usize> {
+    let to_input = |offset: usize| {
+        let chars = expression
+            .char_indices()
+            .take_while(|(i, _)| *i < offset)
+            .count();
+        input
+            .char_indices()
+            .nth(chars)
+            .map_or(input.len(), |(i, _)| i)
+    };
+
+    to_input(span.start)..to_input(span.end)
+}
+
+/// A row of text with a button that removes the item it describes.
+fn removable_row(label: String, on_remove: Message) -> Element<'static, Message> {
+    widget::row::with_capacity(2)
+        .push(text(label).width(Length::Fill))
+        .push(
+            button::icon(icon::from_name("edit-delete-symbolic").size(14))
+                .extra_small()
+                .on_press(on_remove),
+        )
+        .align_y(Alignment::Center)
+        .spacing(8)
+        .into()
 }
 
 fn make_button(label: &str, handler: Option<Message>) -> Element<'_, Message> {
     let text_handler = handler.unwrap_or(Message::KeyPressed(label.to_string()));
 
+    key_button(label).on_press(text_handler).into()
+}
+
+/// A keypad button that inserts the text of key, or that of shifted while the 2nd key is active.
+/// Each is a label and the text it inserts.
+fn make_shift_button(
+    shift: bool,
+    key: (&'static str, &'static str),
+    shifted: (&'static str, &'static str),
+) -> Element<'static, Message> {
+    let (label, insert) = if shift { shifted } else { key };
+
+    make_button(label, Some(Message::Insert(insert.to_string())))
+}
+
+/// A keypad button that types a digit, disabled when the digit is not valid in radix.
+fn make_digit_button(digit: &'static str, radix: Radix) -> Element<'static, Message> {
+    let valid = digit.chars().all(|ch| radix.is_digit(ch));
+
+    key_button(digit)
+        .on_press_maybe(valid.then(|| Message::Insert(digit.to_string())))
+        .into()
+}
+
+fn key_button(label: &str) -> widget::Button<'_, Message> {
     button::custom(
         text(label)
             .size(20)
@@ -506,8 +1502,6 @@ fn make_button(label: &str, handler: Option<Message>) -> Element<'_, Message> {
     )
     .width(60)
     .height(40)
-    .on_press(text_handler)
-    .into()
 }
 
 // Function to create the button with an SVG icon
@@ -554,15 +1548,46 @@ impl AppModel {
 
     /// Evaluate the current input and update the result and history
     pub fn evaluate_input(&mut self) -> Task<cosmic::Action<Message>> {
-        let expression = self
-            .input
-            .replace('×', "*")
-            .replace('÷', "/")
-            .replace('−', "-");
-        match evaluate(expression) {
+        // Input that is missing an operand, as in `3−`, is left to be finished
+        let Some(input) = self.closed_input.clone() else {
+            return Task::none();
+        };
+        let expression = to_expression(&input);
+        let context = self.evaluation_context();
+        let mode = self
+            .nav
+            .active_data::<Page>()
+            .map_or("", Page::as_str)
+            .to_string();
+        let task = match self.environment.evaluate(expression.clone(), &context) {
+            Ok(result) if result.is_definition() => {
+                // Keep the whole definition, so that loading it from the history defines the
+                // function again, and leave the result unchanged
+                if let Some((signature, body)) = input.split_once('=') {
+                    self.push_history(HistoryEntry::new(
+                        format!("{} = {}", signature.trim(), body.trim()),
+                        String::new(),
+                        None,
+                        mode,
+                    ));
+                }
+                self.input.clear();
+                self.save_functions();
+                cosmic::iced::widget::scrollable::snap_to(
+                    Id::new(HISTORY_ID),
+                    cosmic::iced::widget::scrollable::RelativeOffset::END,
+                )
+            }
             Ok(result) => {
-                self.result = result.value();
-                self.history.push((self.input.clone(), self.result.clone()));
+                self.result = result.format(&self.format);
+                self.fraction = result.fraction();
+                self.value = Some(result);
+                self.push_history(HistoryEntry::new(
+                    input,
+                    self.result.clone(),
+                    self.fraction.clone(),
+                    mode,
+                ));
                 self.input.clear();
                 cosmic::iced::widget::scrollable::snap_to(
                     Id::new(HISTORY_ID),
@@ -570,11 +1595,326 @@ impl AppModel {
                 )
             }
             Err(err) => {
-                self.result = err;
+                self.result = err.to_string();
+                self.value = None;
+                self.fraction = None;
+                self.error_span = Some(input_range(&self.input, &expression, err.span().into()));
                 Task::none()
             }
+        };
+
+        self.clear_preview();
+        task
+    }
+
+    /// The result as shown: a fraction if requested and available, otherwise in decimal.
+    fn displayed_result(&self) -> &str {
+        match (&self.fraction, self.show_fractions) {
+            (Some(fraction), true) => fraction,
+            _ => &self.result,
         }
     }
+
+    /// The input with the operator symbols of the keypad replaced by those calclib reads.
+    fn expression(&self) -> String {
+        to_expression(&self.input)
+    }
+
+    /// The `=` key, disabled while the input is missing an operand, as in `3−`. Input that is only
+    /// missing closing parentheses can be evaluated, as they are added.
+    fn equals_button(&self) -> Element<'_, Message> {
+        let enabled = self.closed_input.is_some();
+
+        key_button("=")
+            .on_press_maybe(enabled.then(|| Message::KeyPressed("=".to_string())))
+            .into()
+    }
+
+    /// Evaluates the input as it is typed for the preview, leaving the variables, `ans` and the
+    /// history unchanged. Only input that parses is evaluated, away from the interface so that it
+    /// stays responsive while an expensive expression is calculated.
+    fn update_preview(&mut self) -> Task<cosmic::Action<Message>> {
+        if self.input.trim().is_empty() {
+            self.clear_preview();
+            return Task::none();
+        }
+
+        self.preview_generation += 1;
+        self.preview_queued = false;
+        let expression = self.expression();
+        let context = self.evaluation_context();
+
+        // An error about earlier input is not left in place while this one is evaluated
+        if self.preview_error {
+            self.preview = Some(String::new());
+            self.preview_error = false;
+        }
+
+        // Nothing is shown while the expression is unfinished or cannot be read
+        let status = parser::parse(&expression, &context);
+        self.closed_input = close_parens(&self.input, &status);
+        if !matches!(status, ParseStatus::Complete(_)) {
+            self.preview = Some(String::new());
+            return Task::none();
+        }
+
+        // The latest input is previewed once the running evaluation finishes
+        if self.previewing {
+            self.preview_queued = true;
+            return Task::none();
+        }
+
+        self.previewing = true;
+        let generation = self.preview_generation;
+        let mut environment = self.environment.clone();
+        let format = self.format.clone();
+
+        cosmic::task::future(async move {
+            let preview = match environment.evaluate(expression, &context) {
+                Ok(result) if result.is_definition() => None,
+                Ok(result) => Some(Ok(result.format(&format))),
+                Err(err) => Some(Err(err.to_string())),
+            };
+            Message::PreviewReady(generation, preview)
+        })
+    }
+
+    /// Hides the preview, discarding the one being evaluated.
+    fn clear_preview(&mut self) {
+        self.preview = None;
+        self.preview_error = false;
+        self.preview_queued = false;
+        self.preview_generation += 1;
+        let status = parser::parse(&self.expression(), &self.evaluation_context());
+        self.closed_input = close_parens(&self.input, &status);
+    }
+
+    /// The options for evaluating the input on the active page. The Developer page works with
+    /// integers of the selected word size in the selected radix.
+    fn evaluation_context(&self) -> EvaluationContext {
+        match self.nav.active_data::<Page>() {
+            Some(Page::Developer) => EvaluationContext {
+                arithmetic: Arithmetic::Integer {
+                    word: Some(self.word),
+                },
+                radix: self.radix,
+                ..self.context.clone()
+            },
+            _ => self.context.clone(),
+        }
+    }
+
+    /// The bits of the result in the word, least significant first, or all clear if the result is
+    /// not an integer.
+    fn bits(&self) -> Vec<bool> {
+        self.value
+            .as_ref()
+            .and_then(|value| value.bits(self.word))
+            .unwrap_or_else(|| vec![false; self.word.bits as usize])
+    }
+
+    /// The bits of the result in rows of 16, most significant first and grouped by nibble, with a
+    /// button flipping each of them. The grid holds at least 64 bits, and those above the word
+    /// size are disabled.
+    fn bit_grid(&self) -> Element<'_, Message> {
+        let space_s = cosmic::theme::spacing().space_s;
+        let mut bits = self.bits();
+        let word_bits = bits.len();
+        bits.resize(word_bits.max(GRID_BITS), false);
+
+        bits.chunks(16)
+            .enumerate()
+            .rev()
+            .fold(widget::column::with_capacity(8), |column, (row, chunk)| {
+                let first = row * 16;
+
+                // The number of the highest bit of the row labels it
+                let label = text(format!("{}", first + chunk.len() - 1))
+                    .size(10)
+                    .width(Length::Fixed(24.0));
+
+                let nibbles = chunk.chunks(4).enumerate().rev().fold(
+                    widget::row::with_capacity(5).push(label),
+                    |nibbles, (nibble, nibble_bits)| {
+                        let buttons = nibble_bits.iter().enumerate().rev().fold(
+                            widget::row::with_capacity(4),
+                            |buttons, (i, &set)| {
+                                let bit = first + nibble * 4 + i;
+                                buttons.push(
+                                    button::text(if set { "1" } else { "0" })
+                                        .padding(2)
+                                        .on_press_maybe(
+                                            (bit < word_bits).then_some(Message::ToggleBit(bit)),
+                                        ),
+                                )
+                            },
+                        );
+                        nibbles.push(buttons)
+                    },
+                );
+
+                column.push(nibbles.align_y(Alignment::Center).spacing(space_s))
+            })
+            .spacing(4)
+            .into()
+    }
+
+    /// Adds a calculation to the history, dropping the oldest beyond the configured length, and
+    /// persists it.
+    fn push_history(&mut self, entry: HistoryEntry) {
+        self.history.push(entry);
+
+        let length = self.config.history_length.unwrap_or(DEFAULT_HISTORY_LENGTH);
+        // Drop the oldest entries that are not pinned
+        let mut excess = self.history.len().saturating_sub(length);
+        self.history.retain(|entry| {
+            if excess > 0 && !entry.pinned {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        self.recalled = None;
+
+        self.save_history();
+    }
+
+    /// Persists the history to the state file.
+    fn save_history(&self) {
+        if let Some(ref handler) = self.state_handler {
+            let _ = handler.set("history", &self.history);
+        }
+    }
+
+    /// Persists the definitions of the user-defined functions to config.
+    fn save_functions(&mut self) {
+        self.config.functions = self
+            .environment
+            .functions()
+            .map(|(_, function)| function.definition().to_string())
+            .collect();
+        if let Some(ref handler) = self.config_handler {
+            let _ = self.config.write_entry(handler);
+        }
+    }
+
+    /// Lists the history matching the search, newest first after the pinned entries, with buttons
+    /// to load an entry into the input, evaluate it again, pin it and remove it.
+    fn history_drawer(&self) -> Element<'_, Message> {
+        let space_s = cosmic::theme::spacing().space_s;
+
+        let search = text_input::search_input(fl!("search-history"), &self.history_search)
+            .on_input(Message::HistorySearchChanged)
+            .on_clear(Message::HistorySearchChanged(String::new()));
+
+        let mut entries = self
+            .history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| entry.matches(&self.history_search))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| !entry.pinned);
+
+        let rows = entries
+            .into_iter()
+            .map(|(index, entry)| {
+                let pin_icon = if entry.pinned {
+                    "starred-symbolic"
+                } else {
+                    "non-starred-symbolic"
+                };
+
+                widget::row::with_capacity(4)
+                    .push(
+                        button::custom(
+                            widget::column::with_capacity(2)
+                                .push(text(entry.describe(self.show_fractions)))
+                                .push(text(entry.time()).size(10)),
+                        )
+                        .class(cosmic::theme::Button::Text)
+                        .width(Length::Fill)
+                        .on_press(Message::LoadHistoryEntry(index)),
+                    )
+                    .push(widget::tooltip(
+                        button::icon(icon::from_name(pin_icon).size(14))
+                            .extra_small()
+                            .on_press(Message::TogglePinned(index)),
+                        text(fl!("pin")),
+                        widget::tooltip::Position::Left,
+                    ))
+                    .push(widget::tooltip(
+                        button::icon(icon::from_name("view-refresh-symbolic").size(14))
+                            .extra_small()
+                            .on_press(Message::RerunHistoryEntry(index)),
+                        text(fl!("rerun")),
+                        widget::tooltip::Position::Left,
+                    ))
+                    .push(
+                        button::icon(icon::from_name("edit-delete-symbolic").size(14))
+                            .extra_small()
+                            .on_press(Message::RemoveHistoryEntry(index)),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(8)
+                    .into()
+            })
+            .collect::<Vec<Element<'_, Message>>>();
+
+        let list: Element<'_, Message> = if rows.is_empty() {
+            text(fl!("no-history")).into()
+        } else {
+            widget::column::with_children(rows).spacing(4).into()
+        };
+
+        widget::column::with_capacity(3)
+            .push(search)
+            .push(list)
+            .push(button::standard(fl!("clear-history")).on_press(Message::ClearHistory))
+            .spacing(space_s)
+            .into()
+    }
+
+    /// Lists the session variables and user-defined functions with a button to remove each of
+    /// them.
+    fn variables(&self) -> Element<'_, Message> {
+        let space_s = cosmic::theme::spacing().space_s;
+
+        let variables = self
+            .environment
+            .variables()
+            .map(|(name, value)| {
+                removable_row(
+                    format!("{} = {}", name, value.value()),
+                    Message::RemoveVariable(name.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let functions = self
+            .environment
+            .functions()
+            .map(|(name, function)| {
+                removable_row(
+                    function.definition().to_string(),
+                    Message::RemoveFunction(name.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if variables.is_empty() && functions.is_empty() {
+            return text(fl!("no-variables")).into();
+        }
+
+        widget::column::with_capacity(5)
+            .push(widget::text::heading(fl!("variables")))
+            .push(widget::column::with_children(variables).spacing(4))
+            .push(widget::text::heading(fl!("functions")))
+            .push(widget::column::with_children(functions).spacing(4))
+            .push(button::standard(fl!("clear-variables")).on_press(Message::ClearVariables))
+            .spacing(space_s)
+            .into()
+    }
 }
 
 /// The page to display in the application.
@@ -608,11 +1948,28 @@ impl Page {
 pub enum ContextPage {
     #[default]
     About,
+    Variables,
+    History,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
+    Variables,
+    Clear,
+    AllClear,
+    ClearInput,
+    CopyResult,
+    CopyExpression,
+    ClearHistory,
+    Export(ExportFormat),
+    Import,
+    Basic,
+    Advanced,
+    Developer,
+    History,
+    RecallPrevious,
+    RecallNext,
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -621,6 +1978,99 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
+            MenuAction::Variables => Message::ToggleContextPage(ContextPage::Variables),
+            MenuAction::Clear => Message::KeyPressed("C".to_string()),
+            MenuAction::AllClear => Message::KeyPressed("AC".to_string()),
+            MenuAction::ClearInput => Message::ClearInput,
+            MenuAction::CopyResult => Message::CopyResult,
+            MenuAction::CopyExpression => Message::CopyExpression,
+            MenuAction::ClearHistory => Message::ClearHistory,
+            MenuAction::Export(format) => Message::Export(*format),
+            MenuAction::Import => Message::Import,
+            MenuAction::Basic => Message::ModeSelected(Page::Basic.as_str().to_string()),
+            MenuAction::Advanced => Message::ModeSelected(Page::Advanced.as_str().to_string()),
+            MenuAction::Developer => Message::ModeSelected(Page::Developer.as_str().to_string()),
+            MenuAction::History => Message::ToggleContextPage(ContextPage::History),
+            MenuAction::RecallPrevious => Message::RecallPrevious,
+            MenuAction::RecallNext => Message::RecallNext,
         }
     }
 }
+
+/// The keyboard shortcuts of the menu actions.
+fn key_binds() -> HashMap<KeyBind, MenuAction> {
+    let bind = |modifiers: &[Modifier], key: Key, action| {
+        (
+            KeyBind {
+                modifiers: modifiers.to_vec(),
+                key,
+            },
+            action,
+        )
+    };
+    let character = |ch: &str| Key::Character(ch.into());
+
+    HashMap::from([
+        bind(&[], Key::Named(Named::Escape), MenuAction::Clear),
+        bind(
+            &[Modifier::Shift],
+            Key::Named(Named::Escape),
+            MenuAction::AllClear,
+        ),
+        bind(
+            &[Modifier::Ctrl],
+            Key::Named(Named::Backspace),
+            MenuAction::ClearInput,
+        ),
+        bind(&[Modifier::Ctrl], character("c"), MenuAction::CopyResult),
+        bind(
+            &[Modifier::Ctrl, Modifier::Shift],
+            character("c"),
+            MenuAction::CopyExpression,
+        ),
+        bind(&[Modifier::Ctrl], character("1"), MenuAction::Basic),
+        bind(&[Modifier::Ctrl], character("2"), MenuAction::Advanced),
+        bind(&[Modifier::Ctrl], character("3"), MenuAction::Developer),
+        bind(&[Modifier::Ctrl], character("h"), MenuAction::History),
+        bind(&[], Key::Named(Named::ArrowUp), MenuAction::RecallPrevious),
+        bind(&[], Key::Named(Named::ArrowDown), MenuAction::RecallNext),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_follows_selection() {
+        let character = |ch: &str| Key::Character(ch.into());
+        let ctrl = Modifiers::CTRL;
+        let shift = Modifiers::SHIFT;
+        let none = Modifiers::empty();
+
+        // Each key press after the one before, with whether text is then selected
+        let inputs = vec![
+            (none, character("1"), false),
+            (ctrl, character("a"), true),
+            (none, Key::Named(Named::Control), true),
+            (ctrl, character("c"), true),
+            (none, character("2"), false),
+            (shift, Key::Named(Named::ArrowLeft), true),
+            (none, Key::Named(Named::ArrowLeft), false),
+            (shift, Key::Named(Named::Home), true),
+            (none, Key::Named(Named::Escape), false),
+            (ctrl, character("c"), false),
+        ];
+
+        let mut selected = false;
+        for i in inputs {
+            selected = selection_after_key(selected, i.0, &i.1);
+            assert_eq!(selected, i.2, "{:?} {:?}", i.0, i.1);
+        }
+
+        // Ctrl+C without a selection is left to the key binding that copies the result
+        assert!(is_copy(ctrl, &character("c")));
+        assert!(!is_copy(ctrl | shift, &character("c")));
+        assert!(!is_copy(none, &character("c")));
+    }
+}
@@ -6,4 +6,31 @@ use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::Cosmi
 #[version = 1]
 pub struct Config {
     pub page: String,
+    pub angle_unit: String,
+    /// Show exact results as fractions rather than decimals
+    pub fractions: bool,
+    /// Definitions of the user-defined functions, such as "f(x) = x^2 + 1"
+    pub functions: Vec<String>,
+    /// Evaluate with base-10 decimal arithmetic rather than exact fractions
+    pub decimal: bool,
+    /// Decimal places of results in decimal arithmetic, 2 when unset
+    pub precision: Option<u32>,
+    /// Rounding of results in decimal arithmetic: "half-even", "half-up" or "truncate"
+    pub rounding: String,
+    /// Notation of results: "auto", "fixed", "significant", "scientific" or "engineering"
+    pub notation: String,
+    /// Decimal places of fixed notation
+    pub decimals: Option<usize>,
+    /// Significant figures of significant, scientific and engineering notation
+    pub significant: Option<usize>,
+    /// Separate the digits of results into groups of three
+    pub grouping: bool,
+    /// Radix of numbers typed on the Developer page: "hex", "dec", "oct" or "bin"
+    pub radix: String,
+    /// Bits of integers on the Developer page: 8, 16, 32, 64 or 128, 64 when unset
+    pub word_size: Option<u32>,
+    /// Treat integers on the Developer page as unsigned
+    pub unsigned: bool,
+    /// Most calculations kept in the history, 100 when unset
+    pub history_length: Option<usize>,
 }
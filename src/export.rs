@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MIT
+
+use crate::state::HistoryEntry;
+
+/// A file format the history can be written in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+    ];
+
+    /// The extension of files in this format, without a dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    /// Writes entries in this format. Only JSON keeps every field, so that it can be imported.
+    pub fn export(&self, entries: &[HistoryEntry]) -> Result<String, serde_json::Error> {
+        match self {
+            ExportFormat::Csv => Ok(csv(entries)),
+            ExportFormat::Json => serde_json::to_string_pretty(entries),
+            ExportFormat::Markdown => Ok(markdown(entries)),
+        }
+    }
+}
+
+/// Reads entries written by `ExportFormat::Json`.
+pub fn import(json: &str) -> Result<Vec<HistoryEntry>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// A table with a header row and a row for each entry, quoting fields that contain a comma,
+/// quote or line break.
+fn csv(entries: &[HistoryEntry]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut csv = String::from("expression,result,timestamp,mode\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            field(&entry.expression),
+            field(&entry.result),
+            entry.timestamp(),
+            field(&entry.mode)
+        ));
+    }
+
+    csv
+}
+
+/// A Markdown table, escaping the pipes that would otherwise end a cell.
+fn markdown(entries: &[HistoryEntry]) -> String {
+    let cell = |value: &str| value.replace('|', "\\|");
+
+    let mut markdown =
+        String::from("| Expression | Result | Timestamp | Mode |\n|---|---|---|---|\n");
+    for entry in entries {
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            cell(&entry.expression),
+            cell(&entry.result),
+            entry.timestamp(),
+            cell(&entry.mode)
+        ));
+    }
+
+    markdown
+}
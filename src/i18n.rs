@@ -2,6 +2,7 @@
 
 //! Provides localization support for this crate.
 
+use calclib::locale::Locale;
 use i18n_embed::{
     DefaultLocalizer, LanguageLoader, Localizer,
     fluent::{FluentLanguageLoader, fluent_language_loader},
@@ -17,6 +18,14 @@ pub fn init(requested_languages: &[LanguageIdentifier]) {
     }
 }
 
+/// The characters used to write numbers in the first of the requested languages.
+pub fn number_locale(requested_languages: &[LanguageIdentifier]) -> Locale {
+    requested_languages
+        .first()
+        .map(|language| Locale::from_language(&language.to_string()))
+        .unwrap_or_default()
+}
+
 // Get the `Localizer` to be used for localizing this library.
 #[must_use]
 pub fn localizer() -> Box<dyn Localizer> {
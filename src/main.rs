@@ -2,7 +2,9 @@
 
 mod app;
 mod config;
+mod export;
 mod i18n;
+mod state;
 
 fn main() -> cosmic::iced::Result {
     // Get the system's preferred languages.
@@ -11,10 +13,13 @@ fn main() -> cosmic::iced::Result {
     // Enable localizations to be applied.
     i18n::init(&requested_languages);
 
+    // Read and write numbers the way the preferred language does.
+    let locale = i18n::number_locale(&requested_languages);
+
     // Settings for configuring the application window and iced runtime.
     let settings = cosmic::app::Settings::default()
         .size_limits(cosmic::iced::Limits::NONE.width(460.0).height(750.0));
 
-    // Starts the application's event loop with `()` as the application's flags.
-    cosmic::app::run::<app::AppModel>(settings, ())
+    // Starts the application's event loop with the number locale as the application's flags.
+    cosmic::app::run::<app::AppModel>(settings, locale)
 }
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: MIT
+
+use chrono::{DateTime, Local};
+use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Data that is kept between runs but is not a setting, stored under `XDG_STATE_HOME`.
+#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[version = 1]
+pub struct State {
+    /// Past calculations, oldest first
+    pub history: Vec<HistoryEntry>,
+}
+
+/// A calculation shown in the history list.
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub expression: String,
+    /// Empty for a function definition
+    pub result: String,
+    /// The result as a fraction, if it is exact but not an integer
+    pub fraction: Option<String>,
+    /// When the calculation was made, in seconds since the Unix epoch
+    pub timestamp: i64,
+    /// The page the calculation was made on: "basic", "advanced" or "developer"
+    #[serde(default)]
+    pub mode: String,
+    /// Shown at the top of the history and kept when older entries are dropped
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl HistoryEntry {
+    /// A calculation made now on the page named mode.
+    pub fn new(expression: String, result: String, fraction: Option<String>, mode: String) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or_default();
+
+        Self {
+            expression,
+            result,
+            fraction,
+            timestamp,
+            mode,
+            pinned: false,
+        }
+    }
+
+    /// The result as a fraction if requested and available, otherwise in decimal.
+    pub fn display(&self, fractions: bool) -> &str {
+        match &self.fraction {
+            Some(fraction) if fractions => fraction,
+            _ => &self.result,
+        }
+    }
+
+    /// The calculation as shown in the history: `2+3 = 5`, or a function definition such as
+    /// `f(x) = x^2`, which has no result.
+    pub fn describe(&self, fractions: bool) -> String {
+        if self.result.is_empty() {
+            self.expression.clone()
+        } else {
+            format!("{} = {}", self.expression, self.display(fractions))
+        }
+    }
+
+    /// Whether the expression or the result contains query, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        self.expression.to_lowercase().contains(&query)
+            || self.result.to_lowercase().contains(&query)
+    }
+
+    /// The local date and time of the calculation in RFC 3339 format.
+    pub fn timestamp(&self) -> String {
+        self.date_time()
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default()
+    }
+
+    /// The local time of the calculation, or its date if it was not made today.
+    pub fn time(&self) -> String {
+        let Some(time) = self.date_time() else {
+            return String::new();
+        };
+
+        if time.date_naive() == Local::now().date_naive() {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%b %-d").to_string()
+        }
+    }
+
+    fn date_time(&self) -> Option<DateTime<Local>> {
+        DateTime::from_timestamp(self.timestamp, 0).map(|time| time.with_timezone(&Local))
+    }
+}